@@ -0,0 +1,147 @@
+// 判定の処理時間
+// 履歴の長さ，コマンドの長さ，タメ時間，技リストの規模ごとに測る
+use command_parser::{Command, Key, Matcher, MoveList, Timing};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const BUFFER: u32 = 10;
+const HOLD: u32 = 10;
+
+const MOVE_LIST: &str = r#"(
+    macros: {
+        "qcf": "p2 > p3 > p6",
+        "qcb": "p2 > p1 > p4",
+        "dp": "p6 > p2 > p3",
+        "360": "p6 > p3 > p2 > p1 > p4 > p7 > p8",
+    },
+    moves: [
+        (name: "fireball", command: "$qcf > pA", priority: 1, category: Special),
+        (name: "hurricane", command: "$qcb > pB", priority: 1, category: Special),
+        (name: "dp", command: "$dp > pA", priority: 2, category: Special),
+        (name: "sonic", command: "c4(45)[10] > p6 > pA", priority: 1, category: Special),
+        (name: "flash", command: "c2(45)[10] > p8 > pB", priority: 1, category: Special),
+        (name: "spd", command: "$360 > pA @ 30", priority: 3, category: Special),
+        (name: "hands", command: "pA*5{30}", priority: 1, category: Special),
+        (name: "super", command: "$qcf > $qcf > pC", priority: 4, category: Super),
+        (name: "dash", command: "dash6[10]", category: System),
+        (name: "backdash", command: "dash4[10]", category: System),
+    ],
+)"#;
+
+// 前に何もしないフレームを詰めて履歴の長さをそろえる
+fn inputs(command: &Command, history: usize) -> Vec<Key> {
+    let inputs = command
+        .generate_inputs(Timing::Slowest, BUFFER, HOLD)
+        .unwrap();
+    let padding = history.saturating_sub(inputs.len());
+    (0..padding).map(|_| Key::NEUTRAL).chain(inputs).collect()
+}
+
+fn history_length(c: &mut Criterion) {
+    let mut group = c.benchmark_group("history_length");
+    let commands = [
+        ("motion", "p2 > p3 > p6 > pA"),
+        ("charge", "c4(45)[10] > p6 > pA"),
+        // 最後まで見ても成立しない
+        ("miss", "p8 > p2 > p8 > pD"),
+    ];
+    let motion = Command::build(commands[0].1).unwrap();
+    for &history in &[60, 120, 300, 600] {
+        group.throughput(Throughput::Elements(history as u64));
+        for &(name, command) in &commands {
+            let command = Command::build(command).unwrap();
+            let inputs = match name {
+                "miss" => inputs(&motion, history),
+                _ => inputs(&command, history),
+            };
+            group.bench_with_input(BenchmarkId::new(name, history), &inputs, |b, inputs| {
+                b.iter(|| command.judge_slice(black_box(inputs), BUFFER, HOLD))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn command_length(c: &mut Criterion) {
+    let mut group = c.benchmark_group("command_length");
+    let steps = ["p6", "p3", "p2", "p1", "p4", "p7", "p8", "p9"];
+    for len in 1..=steps.len() {
+        let command = Command::build(&steps[..len].join(" > ")).unwrap();
+        let inputs = inputs(&command, 120);
+        group.bench_with_input(BenchmarkId::from_parameter(len), &inputs, |b, inputs| {
+            b.iter(|| command.judge_slice(black_box(inputs), BUFFER, HOLD))
+        });
+    }
+    group.finish();
+}
+
+fn hold_window(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hold_window");
+    for &hold in &[30, 60, 120, 240] {
+        for &(name, prefix) in &[("hold", "h"), ("charge", "c"), ("grace", "h")] {
+            let grace = if name == "grace" { ",3" } else { "" };
+            let command =
+                Command::build(&format!("{}4({}{})[10] > p6 > pA", prefix, hold, grace)).unwrap();
+            let inputs = inputs(&command, 300);
+            group.bench_with_input(BenchmarkId::new(name, hold), &inputs, |b, inputs| {
+                b.iter(|| command.judge_slice(black_box(inputs), BUFFER, HOLD))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn mash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mash");
+    for &count in &[3, 5, 10] {
+        let command = Command::build(&format!("pA*{}", count)).unwrap();
+        let inputs = inputs(&command, 120);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &inputs, |b, inputs| {
+            b.iter(|| command.judge_slice(black_box(inputs), BUFFER, HOLD))
+        });
+    }
+    group.finish();
+}
+
+// 技リスト全体を1F ごとに判定する
+fn move_list(c: &mut Criterion) {
+    let list = MoveList::from_ron(MOVE_LIST).unwrap();
+    let inputs = list
+        .iter()
+        .flat_map(|m| {
+            m.command
+                .generate_inputs(Timing::Random { seed: 0 }, BUFFER, HOLD)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("move_list");
+    group.throughput(Throughput::Elements(inputs.len() as u64));
+    for &capacity in &[60, 120, 300] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(capacity),
+            &inputs,
+            |b, inputs| {
+                b.iter(|| {
+                    let mut matcher = Matcher::new(capacity, BUFFER, HOLD);
+                    let mut matched = 0;
+                    for &input in inputs {
+                        matcher.update(input);
+                        matched += list.judge(&matcher).len();
+                    }
+                    matched
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    history_length,
+    command_length,
+    hold_window,
+    mash,
+    move_list
+);
+criterion_main!(benches);
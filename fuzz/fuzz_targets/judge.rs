@@ -0,0 +1,26 @@
+// 任意の記法と入力で判定が panic しないこと
+// 先頭 1byte を記法の長さ，続く 2byte ずつをフレームごとの入力とする
+#![no_main]
+use command_parser::{Command, Key, Matcher};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let (len, data) = match data.split_first() {
+        Some((&len, data)) if data.len() >= len as usize => (len as usize, data),
+        _ => return,
+    };
+    let (notation, inputs) = data.split_at(len);
+    let command = match std::str::from_utf8(notation).map(Command::build) {
+        Ok(Ok(command)) => command,
+        _ => return,
+    };
+
+    let mut matcher = Matcher::new(120, 10, 10);
+    for input in inputs.chunks_exact(2) {
+        matcher.update(Key::from_bits_truncate(u16::from_le_bytes([
+            input[0], input[1],
+        ])));
+        let _ = matcher.judge(&command);
+        let _ = command.judge_inputs(matcher.inputs(), 0, 0);
+    }
+});
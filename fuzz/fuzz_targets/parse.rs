@@ -0,0 +1,15 @@
+// 任意の文字列で記法の解析が panic しないこと
+// 解析できたものは再び解析しても同じになること
+#![no_main]
+use command_parser::{serialize, Command};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(notation) = std::str::from_utf8(data) {
+        if let Ok(command) = Command::build(notation) {
+            let serialized = serialize(&command).unwrap();
+            let deserialized: Command = ron::de::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, command);
+        }
+    }
+});
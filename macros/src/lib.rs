@@ -0,0 +1,232 @@
+// 記法をコンパイル時に解析して Command を組み立てるマクロ
+// command!("h4(60)[10] > p6[10] > pC") は Command::build と同じ Command になる
+extern crate proc_macro;
+
+use command_parser::{
+    syntax::{SyntaxNode, SyntaxTree, TokenKind},
+    Command, CommandKey, Error, Key, ParseError,
+};
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::ops::Range;
+use syn::{parse_macro_input, LitStr};
+
+#[proc_macro]
+pub fn command(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let source = literal.value();
+    match Command::build(&source) {
+        Ok(command) => expand_command(&command).into(),
+        Err(err) => {
+            let range = error_range(&source, &err);
+            let span = range
+                .clone()
+                .and_then(|range| literal_span(&literal, range))
+                .unwrap_or_else(|| literal.span());
+            syn::Error::new(span, error_message(&source, &err, range))
+                .to_compile_error()
+                .into()
+        }
+    }
+}
+
+fn expand_command(command: &Command) -> TokenStream2 {
+    let keys = command.keys().map(expand_command_key);
+    let max_duration = expand_option(command.max_duration());
+    quote! {
+        ::command_parser::Command::new([#(#keys),*]).with_max_duration(#max_duration)
+    }
+}
+
+fn expand_command_key(key: &CommandKey) -> TokenStream2 {
+    match *key {
+        CommandKey::Push { key, buffer_frame } => {
+            let key = expand_key(key);
+            let buffer_frame = expand_option(buffer_frame);
+            quote! {
+                ::command_parser::CommandKey::Push { key: #key, buffer_frame: #buffer_frame }
+            }
+        }
+        CommandKey::Release { key, buffer_frame } => {
+            let key = expand_key(key);
+            let buffer_frame = expand_option(buffer_frame);
+            quote! {
+                ::command_parser::CommandKey::Release { key: #key, buffer_frame: #buffer_frame }
+            }
+        }
+        CommandKey::Hold {
+            key,
+            buffer_frame,
+            hold_frame,
+            grace_frame,
+            direction_class,
+        } => {
+            let key = expand_key(key);
+            let buffer_frame = expand_option(buffer_frame);
+            let hold_frame = expand_option(hold_frame);
+            let grace_frame = expand_option(grace_frame);
+            quote! {
+                ::command_parser::CommandKey::Hold {
+                    key: #key,
+                    buffer_frame: #buffer_frame,
+                    hold_frame: #hold_frame,
+                    grace_frame: #grace_frame,
+                    direction_class: #direction_class,
+                }
+            }
+        }
+        CommandKey::On { key } => {
+            let key = expand_key(key);
+            quote! { ::command_parser::CommandKey::On { key: #key } }
+        }
+        CommandKey::Off { key } => {
+            let key = expand_key(key);
+            quote! { ::command_parser::CommandKey::Off { key: #key } }
+        }
+        CommandKey::Mash {
+            key,
+            count,
+            window_frame,
+        } => {
+            let key = expand_key(key);
+            let window_frame = expand_option(window_frame);
+            quote! {
+                ::command_parser::CommandKey::Mash {
+                    key: #key,
+                    count: #count,
+                    window_frame: #window_frame,
+                }
+            }
+        }
+    }
+}
+
+fn expand_key(key: Key) -> TokenStream2 {
+    let bits = key.bits();
+    quote! { ::command_parser::Key::from_bits_truncate(#bits) }
+}
+
+fn expand_option(value: Option<u32>) -> TokenStream2 {
+    match value {
+        Some(value) => quote! { ::core::option::Option::Some(#value) },
+        None => quote! { ::core::option::Option::None },
+    }
+}
+
+// エラーの原因になった記法の範囲 (バイト位置)
+fn error_range(source: &str, err: &Error) -> Option<Range<usize>> {
+    match err {
+        Error::Parse(ParseError::NotComplete { rest }) if source.ends_with(rest.as_str()) => {
+            // 読めなかった最初のキー入力 (直前の > と空白は除く)
+            let step = rest.trim_start_matches(|c: char| c == '>' || c.is_whitespace());
+            let start = source.len() - step.len();
+            let len = step.find('>').unwrap_or(step.len());
+            Some(start..start + step[..len].trim_end().len().max(1))
+        }
+        Error::Parse(ParseError::UndefinedMacro { name }) => {
+            let reference = format!("${}", name);
+            let start = source.find(&reference)?;
+            Some(start..start + reference.len())
+        }
+        Error::Validation(diagnostic) => step_range(source, diagnostic.step?),
+        _ => None,
+    }
+}
+
+// 展開後のキー入力の番号から記法の範囲を探す
+// tap と dash は複数のキー入力になる
+fn step_range(source: &str, step: usize) -> Option<Range<usize>> {
+    let tree = SyntaxTree::parse(source).ok()?;
+    let mut index = 0;
+    for node in tree.nodes() {
+        let tokens = match node {
+            SyntaxNode::Step(tokens) => tokens,
+            _ => continue,
+        };
+        let len = match tokens.iter().find(|token| token.kind == TokenKind::Prefix) {
+            Some(token) if token.text == "tap" => 2,
+            Some(token) if token.text == "dash" => 3,
+            _ => 1,
+        };
+        if step < index + len {
+            return Some(tokens.first()?.span.start..tokens.last()?.span.end);
+        }
+        index += len;
+    }
+    None
+}
+
+// 文字列リテラルの中の範囲を指す
+// エスケープを含む場合や，コンパイラが対応していない場合はリテラル全体になる
+fn literal_span(literal: &LitStr, range: Range<usize>) -> Option<Span> {
+    let token = literal.token();
+    let repr = token.to_string();
+    if repr.contains('\\') {
+        return None;
+    }
+    let offset = repr.find('"')? + 1;
+    token.subspan(offset + range.start..offset + range.end)
+}
+
+// 範囲が分かるときは該当する行に印をつける
+fn error_message(source: &str, err: &Error, range: Option<Range<usize>>) -> String {
+    let range = match range {
+        Some(range) => range,
+        None => return err.to_string(),
+    };
+    let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[range.start..]
+        .find('\n')
+        .map_or(source.len(), |i| range.start + i);
+    let line = &source[line_start..line_end];
+    let column = source[line_start..range.start].chars().count();
+    let width = source[range.start..range.end.min(line_end)]
+        .chars()
+        .count()
+        .max(1);
+    format!(
+        "{}\n  {}\n  {}{}",
+        err,
+        line,
+        " ".repeat(column),
+        "^".repeat(width)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range_of(source: &str) -> Option<Range<usize>> {
+        let err = Command::build(source).unwrap_err();
+        error_range(source, &err)
+    }
+
+    #[test]
+    fn error_range_parse() {
+        let source = "p2 > pX > p6";
+        assert_eq!(range_of(source), Some(5..7));
+        let source = "p2 > $qcf";
+        assert_eq!(range_of(source), Some(5..9));
+    }
+
+    #[test]
+    fn error_range_validation() {
+        // E001 は4つ目のキー入力 (dash は3つに展開される)
+        let source = "dash6 > h4(0)[10] > p6";
+        assert_eq!(&source[range_of(source).unwrap()], "h4(0)[10]");
+    }
+
+    #[test]
+    fn message_marks_range() {
+        let source = "p2 > pX > p6";
+        let err = Command::build(source).unwrap_err();
+        let message = error_message(source, &err, error_range(source, &err));
+        assert!(
+            message.ends_with("\n  p2 > pX > p6\n       ^^"),
+            "{}",
+            message
+        );
+    }
+}
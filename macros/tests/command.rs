@@ -0,0 +1,37 @@
+use command_parser::Command;
+use command_parser_macros::command;
+
+#[test]
+fn same_as_build() {
+    let commands = vec![
+        (
+            command!("h4(60)[10] > p6[10] > pC"),
+            "h4(60)[10] > p6[10] > pC",
+        ),
+        (command!("p2 > p3 > p6 > pA @ 20"), "p2 > p3 > p6 > pA @ 20"),
+        (command!("c2(30,3) > c8(5)"), "c2(30,3) > c8(5)"),
+        (command!("dash6[12]"), "dash6[12]"),
+        (
+            command!("pB*3{10} > r6 > n6 > f6A"),
+            "pB*3{10} > r6 > n6 > f6A",
+        ),
+        (
+            command!(
+                r#"# コメントも書ける
+                h4(60)[10] // 後ろ溜め
+                > p6 > pC"#
+            ),
+            "h4(60)[10] > p6 > pC",
+        ),
+    ];
+    for (command, notation) in commands {
+        assert_eq!(command, Command::build(notation).unwrap(), "{}", notation);
+    }
+}
+
+#[test]
+fn judge() {
+    let command = command!("p2 > p3 > p6 > pA");
+    let inputs = "2 3 6 A".parse::<command_parser::InputScript>().unwrap();
+    assert!(command.judge_inputs(inputs.into_iter(), 10, 10));
+}
@@ -0,0 +1,7 @@
+// 記法の誤りがコンパイルエラーになり，該当する範囲を指すこと
+// 期待する出力を更新するときは TRYBUILD=overwrite cargo test
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,5 @@
+use command_parser_macros::command;
+
+fn main() {
+    let _ = command!("p2 > $qcf");
+}
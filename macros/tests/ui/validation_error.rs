@@ -0,0 +1,5 @@
+use command_parser_macros::command;
+
+fn main() {
+    let _ = command!("dash6 > h4(0)[10] > p6");
+}
@@ -0,0 +1,393 @@
+// 記法ファイルの検証と整形を行うコマンドラインツール
+use command_parser::{
+    deserialize_structured, format_command, serialize, serialize_structured, Command, CommandKey,
+    FormatStyle, Key, MoveList, Severity,
+};
+use std::{ops::Range, process::exit};
+
+// 読み込みや引数のエラーは表示するだけなのでまとめて扱う
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const USAGE: &str = "usage:
+    command-parser-cli check [--deny-warnings] <move_list.ron>...
+    command-parser-cli fmt [--check] [--compact] <file | move_list.ron>...
+    command-parser-cli explain <notation>
+    command-parser-cli convert [--to notation|structured] <command | ->";
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.split_first() {
+        Some((sub, rest)) => match sub.as_str() {
+            "check" => check(rest),
+            "fmt" => fmt(rest),
+            "explain" => explain(rest),
+            "convert" => convert(rest),
+            "-h" | "--help" | "help" => {
+                println!("{}", USAGE);
+                Ok(true)
+            }
+            _ => Err(format!("unknown subcommand: {}", sub).into()),
+        },
+        None => Err("no subcommand".into()),
+    };
+
+    match result {
+        Ok(true) => {}
+        Ok(false) => exit(1),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
+}
+
+// オプションとそれ以外に分ける
+fn split_args<'a>(args: &'a [String], flags: &[&str]) -> Result<(Vec<&'a str>, Vec<&'a str>)> {
+    let mut options = vec![];
+    let mut rest = vec![];
+    for arg in args {
+        if flags.contains(&arg.as_str()) {
+            options.push(arg.as_str());
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option: {}", arg).into());
+        } else {
+            rest.push(arg.as_str());
+        }
+    }
+    Ok((options, rest))
+}
+
+fn read_input(path: &str) -> Result<String> {
+    if path == "-" {
+        let mut input = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)?;
+        Ok(input)
+    } else {
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
+
+// 技リストを読み込んで検証結果を表示する
+// 読み込めなければ失敗
+fn check(args: &[String]) -> Result<bool> {
+    let (options, files) = split_args(args, &["--deny-warnings"])?;
+    if files.is_empty() {
+        return Err("no input file".into());
+    }
+    let deny_warnings = options.contains(&"--deny-warnings");
+
+    let mut ok = true;
+    for file in files {
+        let move_list = match MoveList::from_ron(&read_input(file)?) {
+            Ok(move_list) => move_list,
+            Err(err) => {
+                println!("{}: error: {}", file, err);
+                ok = false;
+                continue;
+            }
+        };
+
+        for mv in move_list.iter() {
+            for diagnostic in mv.command.validate() {
+                let severity = match diagnostic.severity() {
+                    Severity::Warning => "warning",
+                    Severity::Error => "error",
+                };
+                println!("{}: {}: {}: {}", file, mv.name, severity, diagnostic);
+                if deny_warnings || diagnostic.severity() == Severity::Error {
+                    ok = false;
+                }
+            }
+        }
+    }
+
+    Ok(ok)
+}
+
+// ファイルの記法を整形して上書きする
+// 技リストなら各技の command の記法の文字列を1行に整形する
+// --check なら書き換えずに差分の有無だけ返す
+fn fmt(args: &[String]) -> Result<bool> {
+    let (options, files) = split_args(args, &["--check", "--compact"])?;
+    if files.is_empty() {
+        return Err("no input file".into());
+    }
+    let style = if options.contains(&"--compact") {
+        FormatStyle::Compact
+    } else {
+        FormatStyle::Pretty
+    };
+    let check_only = options.contains(&"--check");
+
+    let mut ok = true;
+    for file in files {
+        let source = read_input(file)?;
+        let formatted = if source.trim_start().starts_with('(') {
+            format_move_list(&source)
+        } else {
+            format_command(source.trim_end(), style)
+                .map(|formatted| formatted + "\n")
+                .map_err(Into::into)
+        }
+        .map_err(|err| format!("{}: {}", file, err))?;
+
+        if formatted == source {
+            continue;
+        }
+        if check_only {
+            println!("{}: not formatted", file);
+            ok = false;
+        } else {
+            std::fs::write(file, formatted)?;
+        }
+    }
+
+    Ok(ok)
+}
+
+fn format_move_list(source: &str) -> Result<String> {
+    // 読み込めない技リストは整形しない
+    MoveList::from_ron(source)?;
+
+    let mut formatted = source.to_string();
+    for range in command_literals(source).into_iter().rev() {
+        let notation: String = ron::de::from_str(&source[range.clone()])?;
+        let notation = format_command(notation.trim(), FormatStyle::Compact)?;
+        formatted.replace_range(range, &ron::ser::to_string(&notation)?);
+    }
+    Ok(formatted)
+}
+
+// 技リストの command: に続く文字列リテラルの範囲 (引用符を含む)
+// 構造化形式の command や，コメント，文字列と文字リテラルの中は対象外
+fn command_literals(source: &str) -> Vec<Range<usize>> {
+    let bytes = source.as_bytes();
+    let mut literals = vec![];
+    let mut after_command = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &source[i..];
+        let literal = if bytes[i] == b'"' {
+            Some(string_len(rest))
+        } else {
+            raw_string_len(rest)
+        };
+        if let Some(len) = literal {
+            if after_command {
+                literals.push(i..i + len);
+            }
+            after_command = false;
+            i += len;
+        } else if bytes[i] == b'\'' {
+            // '"' の引用符は文字列の始まりではない
+            after_command = false;
+            i += char_len(rest);
+        } else if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            i += comment.find("*/").map_or(rest.len(), |end| end + 4);
+        } else if bytes[i].is_ascii_alphabetic() || bytes[i] == b'_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let field = rest[len..].trim_start();
+            after_command = &rest[..len] == "command" && field.starts_with(':');
+            i += len;
+            if after_command {
+                i = source.len() - field.len() + 1;
+            }
+        } else {
+            if !bytes[i].is_ascii_whitespace() {
+                after_command = false;
+            }
+            i += 1;
+        }
+    }
+    literals
+}
+
+// "..." の長さ (閉じていなければ残りすべて)
+fn string_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut i = 1;
+    while i < bytes.len() && bytes[i] != b'"' {
+        // エスケープした文字は読み飛ばす
+        i += if bytes[i] == b'\\' { 2 } else { 1 };
+    }
+    (i + 1).min(bytes.len())
+}
+
+// r"..." や r#"..."# の長さ
+fn raw_string_len(rest: &str) -> Option<usize> {
+    let hashes = rest
+        .strip_prefix('r')?
+        .bytes()
+        .take_while(|&b| b == b'#')
+        .count();
+    let body = rest[1 + hashes..].strip_prefix('"')?;
+    let close = format!("\"{}", "#".repeat(hashes));
+    let len = body
+        .find(&close)
+        .map_or(body.len(), |end| end + close.len());
+    Some(2 + hashes + len)
+}
+
+// 'a' や '\'' の長さ
+fn char_len(rest: &str) -> usize {
+    let mut chars = rest.char_indices().skip(1);
+    if let Some((_, '\\')) = chars.next() {
+        chars.next();
+    }
+    chars
+        .find(|&(_, c)| c == '\'')
+        .map_or(rest.len(), |(i, c)| i + c.len_utf8())
+}
+
+// 1つのコマンドを入力手順として表示する
+fn explain(args: &[String]) -> Result<bool> {
+    let (_, rest) = split_args(args, &[])?;
+    let command = Command::build(&rest.join(" "))?;
+    print!("{}", explain_command(&command));
+    Ok(command
+        .validate()
+        .iter()
+        .all(|d| d.severity() == Severity::Warning))
+}
+
+fn explain_command(command: &Command) -> String {
+    let mut output = String::new();
+    for (i, key) in command.keys().enumerate() {
+        output += &format!("{}. {}\n", i + 1, explain_key(key));
+    }
+    if let Some(max_duration) = command.max_duration() {
+        output += &format!("all within {} frames\n", max_duration);
+    }
+    for diagnostic in command.validate() {
+        output += &format!("note: {}\n", diagnostic);
+    }
+    output
+}
+
+fn explain_key(key: &CommandKey) -> String {
+    let within = |buffer_frame: Option<u32>| match buffer_frame {
+        Some(frame) => format!(" within {} frames", frame),
+        None => String::new(),
+    };
+
+    match *key {
+        CommandKey::Push { key, buffer_frame } => format!("press {}{}", key, within(buffer_frame)),
+        CommandKey::Release { key, buffer_frame } => {
+            format!("release {}{}", key, within(buffer_frame))
+        }
+        CommandKey::Hold {
+            key,
+            buffer_frame,
+            hold_frame,
+            grace_frame,
+            direction_class,
+        } => {
+            let mut text = if direction_class {
+                format!("charge {} (any direction containing it)", key)
+            } else {
+                format!("hold {}", key)
+            };
+            if let Some(frame) = hold_frame {
+                text += &format!(" for {} frames", frame);
+            }
+            if let Some(frame) = grace_frame {
+                text += &format!(" allowing {} frames of interruption", frame);
+            }
+            text += &within(buffer_frame);
+            text
+        }
+        CommandKey::On { key } => format!("{} is held", key),
+        CommandKey::Off { key } if key.contains(Key::NEUTRAL) => match key & Key::BUTTONS {
+            buttons if buttons.is_empty() => "stick returns to neutral".to_string(),
+            buttons => format!("stick returns to neutral and {} is not held", buttons),
+        },
+        CommandKey::Off { key } => format!("{} is not held", key),
+        CommandKey::Mash {
+            key,
+            count,
+            window_frame,
+        } => format!("press {} {} times{}", key, count, within(window_frame)),
+    }
+}
+
+// 記法文字列と構造化形式を相互に変換する
+fn convert(args: &[String]) -> Result<bool> {
+    let mut to = None;
+    let mut rest = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => match args.next() {
+                Some(format) => to = Some(format.as_str()),
+                None => return Err("missing value for --to".into()),
+            },
+            _ => rest.push(arg.as_str()),
+        }
+    }
+
+    let input = match rest.as_slice() {
+        ["-"] => read_input("-")?,
+        _ => rest.join(" "),
+    };
+    // 構造化形式なら記法へ，記法なら構造化形式へ変換する
+    let trimmed = input.trim_start();
+    let structured = trimmed.starts_with('(') || trimmed.starts_with('[');
+    let command = if structured || trimmed.starts_with('"') {
+        deserialize_structured(&input)?
+    } else {
+        Command::build(&input)?
+    };
+
+    let output = match to.unwrap_or(if structured { "notation" } else { "structured" }) {
+        "notation" => serialize(&command)?,
+        "structured" => serialize_structured(&command)?,
+        to => return Err(format!("unknown format: {}", to).into()),
+    };
+    println!("{}", output);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_literals_in_move_list() {
+        let source = r##"(
+    // command: "p2" はコメント
+    moves: [
+        (name: "command: \"p6\"", command : "p2>p6"),
+        (name: "dash", command: [Push(key: ["FORWARD"])]),
+        (name: "jab", /* command: "pB" */ command: "p A"),
+        (name: r#"raw "command: "p8""#, command: r#"p2 > p"B""#),
+        (name: "quote", symbol: '"', command: r"p C", tag: '\''),
+    ],
+)"##;
+        let literals = command_literals(source)
+            .into_iter()
+            .map(|range| &source[range])
+            .collect::<Vec<_>>();
+        assert_eq!(
+            literals,
+            vec![r#""p2>p6""#, r#""p A""#, r##"r#"p2 > p"B""#"##, r#"r"p C""#]
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let command = Command::build("c4(60)[10] > p6 > pA*3{20} @ 90").unwrap();
+        assert_eq!(
+            explain_command(&command),
+            "1. charge 4 (any direction containing it) for 60 frames within 10 frames
+2. press 6
+3. press A 3 times within 20 frames
+all within 90 frames
+"
+        );
+    }
+}
@@ -0,0 +1,474 @@
+// 記法を使わずにコマンドを組み立てる
+// Command::builder().charge(Key::BACKWARD).frames(60).buffer(10).then().push(Key::FORWARD)
+// キー入力を追加したあと，then か次のキー入力までは直前のキー入力の設定を変更できる
+use crate::{
+    command::Command,
+    types::{
+        button::Key,
+        input::{dash_keys, tap_keys, CommandKey},
+    },
+    validate::{validate, Diagnostic, Severity},
+};
+use alloc::vec::Vec;
+use core::{fmt, ops::Range};
+
+// よく使う方向入力
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    // 236
+    QuarterCircleForward,
+    // 214
+    QuarterCircleBack,
+    // 623
+    DragonPunch,
+    // 421
+    ReverseDragonPunch,
+    // 41236
+    HalfCircleForward,
+    // 63214
+    HalfCircleBack,
+}
+
+impl Motion {
+    pub fn keys(self) -> &'static [Key] {
+        match self {
+            Motion::QuarterCircleForward => &[Key::DOWN, Key::FD, Key::FORWARD],
+            Motion::QuarterCircleBack => &[Key::DOWN, Key::BD, Key::BACKWARD],
+            Motion::DragonPunch => &[Key::FORWARD, Key::DOWN, Key::FD],
+            Motion::ReverseDragonPunch => &[Key::BACKWARD, Key::DOWN, Key::BD],
+            Motion::HalfCircleForward => {
+                &[Key::BACKWARD, Key::BD, Key::DOWN, Key::FD, Key::FORWARD]
+            }
+            Motion::HalfCircleBack => &[Key::FORWARD, Key::FD, Key::DOWN, Key::BD, Key::BACKWARD],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    // 設定を変更するキー入力がない (最初か then の直後)
+    NoStep { method: &'static str },
+    // キー入力の種類に対して設定できない
+    NotApplicable { method: &'static str, step: usize },
+    TooManyKeys { len: usize, max: usize },
+    EmptyKey { index: usize },
+    NoKeys,
+    Validation(Diagnostic),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::NoStep { method } => write!(f, "{} must follow a key", method),
+            BuildError::NotApplicable { method, step } => {
+                write!(f, "{} is not applicable to step {}", method, step)
+            }
+            BuildError::TooManyKeys { len, max } => {
+                write!(f, "too many keys: {} (max {})", len, max)
+            }
+            BuildError::EmptyKey { index } => write!(f, "empty key at step {}", index),
+            BuildError::NoKeys => write!(f, "command has no keys"),
+            BuildError::Validation(diagnostic) => write!(f, "invalid command: {}", diagnostic),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandBuilder {
+    keys: Vec<CommandKey>,
+    // 設定を変更できるキー入力の範囲
+    current: Option<Range<usize>>,
+    max_duration: Option<u32>,
+    // 最初のエラー以降の操作は無視する
+    error: Option<BuildError>,
+}
+
+impl CommandBuilder {
+    pub fn new() -> Self {
+        CommandBuilder::default()
+    }
+
+    // p
+    pub fn push(self, key: Key) -> Self {
+        self.step(
+            [CommandKey::Push {
+                key,
+                buffer_frame: None,
+            }],
+            1,
+        )
+    }
+
+    // r
+    pub fn release(self, key: Key) -> Self {
+        self.step(
+            [CommandKey::Release {
+                key,
+                buffer_frame: None,
+            }],
+            1,
+        )
+    }
+
+    // h
+    pub fn hold(self, key: Key) -> Self {
+        self.step([hold_key(key, false)], 1)
+    }
+
+    // c: 斜めも含めた方向の分類でタメる
+    pub fn charge(self, key: Key) -> Self {
+        self.step([hold_key(key, true)], 1)
+    }
+
+    // n
+    pub fn on(self, key: Key) -> Self {
+        self.step([CommandKey::On { key }], 1)
+    }
+
+    // f
+    pub fn off(self, key: Key) -> Self {
+        self.step([CommandKey::Off { key }], 1)
+    }
+
+    // p*count
+    pub fn mash(self, key: Key, count: u32) -> Self {
+        self.step(
+            [CommandKey::Mash {
+                key,
+                count,
+                window_frame: None,
+            }],
+            1,
+        )
+    }
+
+    // tap: 設定は最初の押しに対して行う
+    pub fn tap(self, key: Key) -> Self {
+        self.step(tap_keys(key, None), 1)
+    }
+
+    // dash: 設定は最初の押しに対して行う
+    pub fn dash(self, key: Key) -> Self {
+        self.step(dash_keys(key, None), 1)
+    }
+
+    // 方向入力を順に押す: 設定はすべての押しに対して行う
+    pub fn motion(self, motion: Motion) -> Self {
+        let keys = motion.keys();
+        let len = keys.len();
+        self.step(
+            keys.iter().map(|&key| CommandKey::Push {
+                key,
+                buffer_frame: None,
+            }),
+            len,
+        )
+    }
+
+    // [buffer]
+    pub fn buffer(self, frame: u32) -> Self {
+        self.modify("buffer", |key| match key {
+            CommandKey::Push { buffer_frame, .. }
+            | CommandKey::Release { buffer_frame, .. }
+            | CommandKey::Hold { buffer_frame, .. } => {
+                *buffer_frame = Some(frame);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    // タメ時間: (frames)
+    pub fn frames(self, frame: u32) -> Self {
+        self.modify("frames", |key| match key {
+            CommandKey::Hold { hold_frame, .. } => {
+                *hold_frame = Some(frame);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    // タメの途切れの猶予: (frames,grace)
+    pub fn grace(self, frame: u32) -> Self {
+        self.modify("grace", |key| match key {
+            CommandKey::Hold { grace_frame, .. } => {
+                *grace_frame = Some(frame);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    // 連打の受付時間: {window}
+    pub fn window(self, frame: u32) -> Self {
+        self.modify("window", |key| match key {
+            CommandKey::Mash { window_frame, .. } => {
+                *window_frame = Some(frame);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    // 次のキー入力との区切り: >
+    pub fn then(mut self) -> Self {
+        self.close();
+        self
+    }
+
+    // コマンド全体の制限時間: @ frames
+    pub fn within(mut self, max_duration: u32) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    pub fn build(mut self) -> Result<Command, BuildError> {
+        self.close();
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        if self.keys.is_empty() {
+            return Err(BuildError::NoKeys);
+        }
+        let command = Command::checked_new(self.keys)
+            .map_err(|len| BuildError::TooManyKeys {
+                len,
+                max: Command::MAX_KEYS,
+            })?
+            .with_max_duration(self.max_duration);
+        match first_error(&command) {
+            Some(diagnostic) => Err(BuildError::Validation(diagnostic)),
+            None => Ok(command),
+        }
+    }
+
+    // keys を追加して，先頭から configurable 個を設定の対象にする
+    fn step(mut self, keys: impl IntoIterator<Item = CommandKey>, configurable: usize) -> Self {
+        self.close();
+        if self.error.is_some() {
+            return self;
+        }
+        let start = self.keys.len();
+        self.keys.extend(keys);
+        if self.keys.len() > Command::MAX_KEYS {
+            self.error = Some(BuildError::TooManyKeys {
+                len: self.keys.len(),
+                max: Command::MAX_KEYS,
+            });
+            return self;
+        }
+        if let Some(index) = (start..self.keys.len()).find(|&i| self.keys[i].key().is_empty()) {
+            self.error = Some(BuildError::EmptyKey { index });
+            return self;
+        }
+        self.current = Some(start..start + configurable);
+        self
+    }
+
+    fn modify(mut self, method: &'static str, f: impl Fn(&mut CommandKey) -> bool) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        let current = match self.current.clone() {
+            Some(current) => current,
+            None => {
+                self.error = Some(BuildError::NoStep { method });
+                return self;
+            }
+        };
+        if let Some(step) = current.clone().find(|&i| !f(&mut self.keys[i])) {
+            self.error = Some(BuildError::NotApplicable { method, step });
+        }
+        self
+    }
+
+    // 設定の終わったキー入力を検証する
+    fn close(&mut self) {
+        if self.current.take().is_none() || self.error.is_some() {
+            return;
+        }
+        self.error = match Command::checked_new(self.keys.iter().copied()) {
+            Ok(command) => first_error(&command).map(BuildError::Validation),
+            Err(len) => Some(BuildError::TooManyKeys {
+                len,
+                max: Command::MAX_KEYS,
+            }),
+        };
+    }
+}
+
+fn hold_key(key: Key, direction_class: bool) -> CommandKey {
+    CommandKey::Hold {
+        key,
+        buffer_frame: None,
+        hold_frame: None,
+        grace_frame: None,
+        direction_class,
+    }
+}
+
+fn first_error(command: &Command) -> Option<Diagnostic> {
+    validate(command)
+        .into_iter()
+        .find(|diagnostic| diagnostic.severity() == Severity::Error)
+}
+
+// テストは記法の解析を使うので std のときだけ
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{types::build_command, validate::Lint};
+
+    #[test]
+    fn same_as_notation() {
+        let command = Command::builder()
+            .charge(Key::BACKWARD)
+            .frames(60)
+            .buffer(10)
+            .then()
+            .push(Key::FORWARD)
+            .buffer(10)
+            .then()
+            .push(Key::C)
+            .build()
+            .unwrap();
+        assert_eq!(command, build_command("c4(60)[10] > p6[10] > pC").unwrap());
+
+        let command = Command::builder()
+            .motion(Motion::QuarterCircleForward)
+            .buffer(8)
+            .then()
+            .motion(Motion::QuarterCircleForward)
+            .then()
+            .push(Key::A | Key::B)
+            .within(40)
+            .build()
+            .unwrap();
+        assert_eq!(
+            command,
+            build_command("p2[8] > p3[8] > p6[8] > p2 > p3 > p6 > pAB @ 40").unwrap()
+        );
+
+        let command = Command::builder()
+            .dash(Key::FORWARD)
+            .buffer(12)
+            .then()
+            .hold(Key::DOWN)
+            .frames(30)
+            .grace(3)
+            .then()
+            .mash(Key::B, 3)
+            .window(20)
+            .then()
+            .release(Key::B)
+            .then()
+            .on(Key::FORWARD)
+            .then()
+            .off(Key::A)
+            .then()
+            .tap(Key::UP)
+            .build()
+            .unwrap();
+        assert_eq!(
+            command,
+            build_command("dash6[12] > h2(30,3) > pB*3{20} > rB > n6 > fA > tap8").unwrap()
+        );
+    }
+
+    #[test]
+    fn then_is_optional() {
+        // 次のキー入力を追加すると前のキー入力は閉じる
+        let command = Command::builder()
+            .motion(Motion::DragonPunch)
+            .push(Key::C)
+            .build()
+            .unwrap();
+        assert_eq!(command, build_command("p6 > p2 > p3 > pC").unwrap());
+    }
+
+    #[test]
+    fn build_error() {
+        assert_eq!(
+            Command::builder().push(Key::A).frames(10).build(),
+            Err(BuildError::NotApplicable {
+                method: "frames",
+                step: 0
+            })
+        );
+        assert_eq!(
+            Command::builder().push(Key::A).then().buffer(10).build(),
+            Err(BuildError::NoStep { method: "buffer" })
+        );
+        assert_eq!(
+            Command::builder().buffer(10).push(Key::A).build(),
+            Err(BuildError::NoStep { method: "buffer" })
+        );
+        assert_eq!(
+            Command::builder().push(Key::empty()).build(),
+            Err(BuildError::EmptyKey { index: 0 })
+        );
+        assert_eq!(Command::builder().build(), Err(BuildError::NoKeys));
+
+        let mut builder = Command::builder();
+        for _ in 0..Command::MAX_KEYS {
+            builder = builder.push(Key::A);
+        }
+        assert_eq!(
+            builder
+                .clone()
+                .build()
+                .map(|command| command.keys().count()),
+            Ok(Command::MAX_KEYS)
+        );
+        assert_eq!(
+            builder.push(Key::A).build(),
+            Err(BuildError::TooManyKeys {
+                len: Command::MAX_KEYS + 1,
+                max: Command::MAX_KEYS
+            })
+        );
+    }
+
+    #[test]
+    fn build_validation() {
+        // キー入力を閉じた時点の検証エラーを返す
+        let err = Command::builder()
+            .push(Key::A)
+            .then()
+            .hold(Key::BACKWARD)
+            .frames(0)
+            .then()
+            .push(Key::FORWARD)
+            .buffer(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            BuildError::Validation(Diagnostic {
+                lint: Lint::ZeroHold,
+                step: Some(1)
+            })
+        );
+
+        let err = Command::builder()
+            .push(Key::A)
+            .within(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            BuildError::Validation(Diagnostic {
+                lint: Lint::ZeroDuration,
+                step: None
+            })
+        );
+
+        // 警告は許容
+        Command::builder()
+            .hold(Key::NEUTRAL)
+            .frames(60)
+            .build()
+            .unwrap();
+    }
+}
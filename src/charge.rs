@@ -0,0 +1,164 @@
+use crate::types::button::Key;
+
+// タメを管理する方向
+// 4, 2, 6, 8 は斜めを含めた分類でタメる
+const DIRECTIONS: [Key; 8] = [
+    Key::FORWARD,
+    Key::BACKWARD,
+    Key::UP,
+    Key::DOWN,
+    Key::FU,
+    Key::FD,
+    Key::BU,
+    Key::BD,
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+struct Charge {
+    // 現在押し続けているフレーム数
+    held: u32,
+    // 前回押し続けていたフレーム数
+    last_held: u32,
+    // 前回離してからのフレーム数
+    // 押している間は前回離してから今回押すまでのフレーム数
+    released: u32,
+}
+
+// 方向ごとのタメ状態を1Fごとに更新する
+// 巻き戻しのためにそのまま複製できる
+// MatcherState と一緒に保存するので serde 対応も同じく std のとき
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChargeTracker {
+    charges: [Charge; 8],
+    // 離したあとタメを維持するフレーム数
+    // 判定ではタメのバッファより長ければこちらを使う
+    retain_frame: u32,
+}
+
+impl ChargeTracker {
+    pub fn new(retain_frame: u32) -> Self {
+        ChargeTracker {
+            charges: Default::default(),
+            retain_frame,
+        }
+    }
+
+    pub fn update(&mut self, input: Key) {
+        for (direction, charge) in DIRECTIONS.iter().zip(self.charges.iter_mut()) {
+            if input.contains_class(*direction) {
+                charge.held += 1;
+            } else {
+                if charge.held > 0 {
+                    charge.last_held = charge.held;
+                    charge.held = 0;
+                    charge.released = 0;
+                }
+                charge.released = charge.released.saturating_add(1);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.charges = Default::default();
+    }
+
+    pub fn retain_frame(&self) -> u32 {
+        self.retain_frame
+    }
+
+    // 現在押し続けているフレーム数
+    pub fn held(&self, direction: Key) -> u32 {
+        self.get(direction).map(|c| c.held).unwrap_or(0)
+    }
+
+    // 離してからのフレーム数 (押している間は None)
+    pub fn released(&self, direction: Key) -> Option<u32> {
+        self.get(direction)
+            .filter(|c| c.held == 0 && c.last_held > 0)
+            .map(|c| c.released)
+    }
+
+    // 現在有効なタメのフレーム数
+    // 離してから retain_frame 以内なら前回のタメを維持する
+    pub fn charge(&self, direction: Key) -> u32 {
+        match self.get(direction) {
+            Some(c) if c.held > 0 => c.held,
+            Some(c) if c.released <= self.retain_frame => c.last_held,
+            _ => 0,
+        }
+    }
+
+    // 最新の入力から cursor F さかのぼった位置より前で，最後にタメていた区間
+    // (区間の終わりまでのフレーム数, 区間のフレーム数) を返す
+    // 記録している区間より前の場合は None
+    pub(crate) fn held_before(&self, direction: Key, cursor: u32) -> Option<(u32, u32)> {
+        let c = self.get(direction)?;
+        if c.held > 0 && cursor < c.held {
+            return Some((0, c.held - cursor));
+        }
+        let last_end = c.held + c.released;
+        if c.last_held > 0 && cursor < last_end {
+            return Some((last_end - cursor, c.last_held));
+        }
+        None
+    }
+
+    fn get(&self, direction: Key) -> Option<&Charge> {
+        DIRECTIONS
+            .iter()
+            .position(|d| *d == direction)
+            .map(|i| &self.charges[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charge() {
+        let mut tracker = ChargeTracker::new(8);
+        let inputs = (0..30)
+            .map(|_| Key::BACKWARD)
+            .chain((0..20).map(|_| Key::BD))
+            .chain((0..5).map(|_| Key::empty()));
+        for input in inputs {
+            tracker.update(input);
+        }
+        assert_eq!(tracker.held(Key::BACKWARD), 0);
+        assert_eq!(tracker.released(Key::BACKWARD), Some(5));
+        assert_eq!(tracker.charge(Key::BACKWARD), 50);
+        assert_eq!(tracker.charge(Key::DOWN), 20);
+        assert_eq!(tracker.charge(Key::BD), 20);
+        assert_eq!(tracker.charge(Key::FORWARD), 0);
+
+        // 維持時間を過ぎるとタメが無くなる
+        for _ in 0..4 {
+            tracker.update(Key::empty());
+        }
+        assert_eq!(tracker.charge(Key::BACKWARD), 0);
+
+        tracker.update(Key::BU);
+        assert_eq!(tracker.held(Key::BACKWARD), 1);
+        assert_eq!(tracker.released(Key::BACKWARD), None);
+        assert_eq!(tracker.charge(Key::UP), 1);
+    }
+
+    #[test]
+    fn held_before() {
+        let mut tracker = ChargeTracker::new(0);
+        let inputs = (0..30)
+            .map(|_| Key::BACKWARD)
+            .chain((0..5).map(|_| Key::empty()))
+            .chain((0..10).map(|_| Key::BACKWARD));
+        for input in inputs {
+            tracker.update(input);
+        }
+        assert_eq!(tracker.held_before(Key::BACKWARD, 0), Some((0, 10)));
+        assert_eq!(tracker.held_before(Key::BACKWARD, 4), Some((0, 6)));
+        assert_eq!(tracker.held_before(Key::BACKWARD, 12), Some((3, 30)));
+        assert_eq!(tracker.held_before(Key::BACKWARD, 15), None);
+    }
+}
@@ -1,134 +1,809 @@
-use crate::types::{build_command, button::Key, input::CommandKey};
+#[cfg(feature = "alloc")]
+use crate::{
+    builder::CommandBuilder,
+    generate::{generate, Timing},
+    validate::{validate, Diagnostic},
+};
+use crate::{
+    charge::ChargeTracker,
+    config::JudgeConfig,
+    types::{button::Key, input::CommandKey},
+};
+#[cfg(feature = "std")]
+use crate::{
+    error::{Error, ParseError},
+    types::{build_command, build_command_with_macros, Macros},
+};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use arrayvec::ArrayVec;
 
-#[derive(Debug)]
+// キー入力は固定長で持ち，判定中にヒープを確保しない
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Command {
-    keys: Vec<CommandKey>,
+    keys: ArrayVec<CommandKey, { Command::MAX_KEYS }>,
+    // コマンド全体の入力制限時間
+    max_duration: Option<u32>,
+}
+
+// コマンド成立時の情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchResult {
+    // 最初のキー入力から最後のキー入力までのフレーム数
+    pub duration: u32,
+    pub max_duration: Option<u32>,
 }
 
 impl Command {
-    pub fn build(command: &str) -> Result<Self, failure::Error> {
-        Ok(build_command(command)?)
+    // 1つのコマンドに含められるキー入力の数
+    pub const MAX_KEYS: usize = 32;
+
+    #[cfg(feature = "std")]
+    pub fn build(command: &str) -> Result<Self, Error> {
+        build_command(command)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn build_with_macros(command: &str, macros: &Macros) -> Result<Self, Error> {
+        build_command_with_macros(command, macros)
+    }
+
+    // MAX_KEYS を超えると panic する
+    // 記法からの変換や try_new ではエラーになる
+    pub fn new(keys: impl IntoIterator<Item = CommandKey>) -> Self {
+        Command {
+            keys: keys.into_iter().collect(),
+            max_duration: None,
+        }
+    }
+
+    // MAX_KEYS を超えると TooManyKeys
+    #[cfg(feature = "std")]
+    pub fn try_new(keys: impl IntoIterator<Item = CommandKey>) -> Result<Self, Error> {
+        Command::checked_new(keys).map_err(|len| {
+            ParseError::TooManyKeys {
+                len,
+                max: Command::MAX_KEYS,
+            }
+            .into()
+        })
     }
 
-    pub fn new(keys: Vec<CommandKey>) -> Self {
-        Command { keys }
+    // MAX_KEYS を超えたときはキー入力の数を返す
+    // std のない builder からも使う
+    #[cfg(feature = "alloc")]
+    pub(crate) fn checked_new(keys: impl IntoIterator<Item = CommandKey>) -> Result<Self, usize> {
+        let mut keys = keys.into_iter();
+        let command = Command::new(keys.by_ref().take(Command::MAX_KEYS));
+        match keys.count() {
+            0 => Ok(command),
+            rest => Err(Command::MAX_KEYS + rest),
+        }
+    }
+
+    // キー入力を順に追加して組み立てる
+    #[cfg(feature = "alloc")]
+    pub fn builder() -> CommandBuilder {
+        CommandBuilder::new()
+    }
+
+    pub fn with_max_duration(mut self, max_duration: Option<u32>) -> Self {
+        self.max_duration = max_duration;
+        self
     }
 
     pub fn keys(&self) -> impl DoubleEndedIterator<Item = &CommandKey> {
         self.keys.iter()
     }
 
+    pub fn max_duration(&self) -> Option<u32> {
+        self.max_duration
+    }
+
+    // 判定できない，または意味のないキー入力を検出する
+    #[cfg(feature = "alloc")]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate(self)
+    }
+
+    // 成立する入力を作る
+    // 制限時間に収まらないなど成立させられなければ None
+    #[cfg(feature = "alloc")]
+    pub fn generate_inputs(
+        &self,
+        timing: Timing,
+        default_buffer: u32,
+        default_hold: u32,
+    ) -> Option<Vec<Key>> {
+        generate(self, timing, default_buffer, default_hold)
+    }
+
+    // 古い順に並んだ入力履歴から判定する
+    // 履歴を固定長の配列などで持てば，1F ごとの判定でヒープを確保しない
+    pub fn judge_slice(
+        &self,
+        history: &[Key],
+        default_buffer: u32,
+        default_hold: u32,
+    ) -> Option<MatchResult> {
+        self.judge(history.iter().copied(), default_buffer, default_hold)
+    }
+
     // キー入力バッファからコマンドが成立したか判定
+    // 1度しか読めないイテレータも受け付けるよう，入力を集めてから判定する
+    // ヒープを確保せずに判定するには judge_slice を使う
+    #[cfg(feature = "alloc")]
     pub fn judge_inputs(
         &self,
         inputs: impl DoubleEndedIterator<Item = Key>,
         default_buffer: u32,
         default_hold: u32,
     ) -> bool {
+        let history = inputs.collect::<Vec<_>>();
+        self.judge_slice(&history, default_buffer, default_hold)
+            .is_some()
+    }
+
+    // キー入力バッファからコマンドが成立したか判定し，成立時の情報を返す
+    // 猶予つきのタメ，同時押し，離しでの成立で読み戻すために入力を複製できる必要がある
+    pub fn judge(
+        &self,
+        inputs: impl DoubleEndedIterator<Item = Key> + Clone,
+        default_buffer: u32,
+        default_hold: u32,
+    ) -> Option<MatchResult> {
+        self.judge_with_charge(inputs, default_buffer, default_hold, None)
+    }
+
+    // 同じ入力で更新したタメ状態があれば，斜めを含めたタメの判定に使う
+    pub fn judge_with_charge(
+        &self,
+        inputs: impl DoubleEndedIterator<Item = Key> + Clone,
+        default_buffer: u32,
+        default_hold: u32,
+        charge: Option<&ChargeTracker>,
+    ) -> Option<MatchResult> {
+        let config = JudgeConfig::new(default_buffer, default_hold);
+        self.judge_with_config(inputs, &config, charge)
+    }
+
+    // 判定の既定値と入力の扱いを指定して判定する
+    pub fn judge_with_config(
+        &self,
+        inputs: impl DoubleEndedIterator<Item = Key> + Clone,
+        config: &JudgeConfig,
+        charge: Option<&ChargeTracker>,
+    ) -> Option<MatchResult> {
+        let socd = config.socd;
+        let inputs = inputs.map(move |input| socd.resolve(input));
         // 逆順に判定していく
         // 消費しながら使い回すので参照取得
-        let mut inputs_rev = inputs.rev();
-        let inputs_rev = inputs_rev.by_ref();
-        self.keys().rev().all(|key| match key {
-            &CommandKey::Push { key, buffer_frame } => {
+        // 押しっぱなし区間の直前の入力は次のキーの判定に使うので先読みする
+        let mut inputs_rev = ReverseInputs::new(inputs.rev());
+        let inputs_rev = &mut inputs_rev;
+        // 各キーの成立位置 (最新の入力からのフレーム数) を返す
+        let positions = self.keys().rev().map(|key| match *key {
+            CommandKey::Push { key, buffer_frame } => {
                 log::trace!("push: Key = {:?}, buffer = {:?}", key, buffer_frame);
-                let buffer_frame = buffer_frame.unwrap_or(default_buffer);
+                let buffer_frame = config.buffer_frame(buffer_frame);
                 // ボタンを押したときは直前がそのボタンじゃなかったとき
                 // 最後の入力を見つけたあと，その後のカウント数と合わせてバッファフレーム内に収まるか
-                let position = inputs_rev
-                    .position(|input| input.contains(key))
-                    .map(|p| p as u32);
+                // ボタンだけの押しは，離しがバッファフレーム以内でもOK
+                // 離しで成立したときは押していた区間を前のキーの判定に使うので戻す
+                let negative_edge = config.negative_edge && Key::BUTTONS.contains(key);
+                let start = if negative_edge {
+                    Some(inputs_rev.clone())
+                } else {
+                    None
+                };
+                let position = position_pressed(inputs_rev, key, config.simultaneous)?;
 
-                let first_input_count =
-                    inputs_rev.take_while(|input| input.contains(key)).count() as u32;
+                let first_input_count = skip_held(inputs_rev, key);
 
                 // 最後の入力がバッファフレーム以内ならOK
-                position
-                    .map(|p| p + first_input_count < buffer_frame)
-                    .unwrap_or(false)
+                if position + first_input_count < buffer_frame {
+                    return Some(inputs_rev.last_position());
+                }
+                match start {
+                    Some(start) if position > 0 && position <= buffer_frame => {
+                        *inputs_rev = start;
+                        inputs_rev.nth(position as usize - 1);
+                        Some(inputs_rev.last_position())
+                    }
+                    _ => None,
+                }
             }
-            &CommandKey::Release { key, buffer_frame } => {
+            CommandKey::Release { key, buffer_frame } => {
                 log::trace!("release: Key = {:?}, buffer = {:?}", key, buffer_frame);
-                let buffer_frame = buffer_frame.unwrap_or(default_buffer);
+                let buffer_frame = config.buffer_frame(buffer_frame);
                 // ボタン離しは最後に入力があったもののあとなので，最後の入力位置を探す
                 let position = inputs_rev
                     .position(|input| input.contains(key))
                     .map(|p| p as u32);
                 // 見つけたのは最後の入力なので，その直後からバッファフレーム内ならOK
                 position
-                    .map(|p| p > 0 && p < buffer_frame + 1)
-                    .unwrap_or(false)
+                    .filter(|&p| p > 0 && p <= buffer_frame)
+                    .map(|_| inputs_rev.last_position())
             }
-            &CommandKey::Hold {
+            CommandKey::Hold {
                 key,
                 buffer_frame,
                 hold_frame,
+                grace_frame,
+                direction_class,
             } => {
                 log::trace!(
-                    "hold: Key = {:?}, hold = {:?}, buffer = {:?}",
+                    "hold: Key = {:?}, hold = {:?}, buffer = {:?}, grace = {:?}, class = {:?}",
                     key,
                     hold_frame,
-                    buffer_frame
+                    buffer_frame,
+                    grace_frame,
+                    direction_class
                 );
-                let buffer_frame = buffer_frame.unwrap_or(default_buffer);
-                let hold_frame = hold_frame.unwrap_or(default_hold);
-                let position = inputs_rev
-                    .position(|input| input.contains(key))
-                    .map(|p| p as u32);
+                let buffer_frame = config.buffer_frame(buffer_frame);
+                let hold_frame = hold_frame.unwrap_or(config.hold);
+                let grace_frame = grace_frame.unwrap_or(0);
+
+                // 途切れを許容しない方向のタメはタメ状態から判定する
+                let held_before = charge
+                    .filter(|_| direction_class && grace_frame == 0)
+                    .and_then(|charge| charge.held_before(key, inputs_rev.consumed));
+                if let Some((position, hold_count)) = held_before {
+                    // 離した位置が履歴より前なら，前のキーを判定できないので不成立
+                    inputs_rev.clone().nth(position as usize)?;
+                    // 離したあとタメを維持する間はバッファを過ぎてもよい
+                    let retain_frame = charge.map_or(0, ChargeTracker::retain_frame);
+                    let buffer_ok = position <= buffer_frame.max(retain_frame);
+                    let hold_ok = hold_count >= hold_frame;
+                    let released = inputs_rev.consumed + position;
+                    // タメていた区間は読み飛ばす
+                    inputs_rev.nth((position + hold_count - 1) as usize);
+                    return Some(released).filter(|_| buffer_ok && hold_ok);
+                }
+
+                let held = |input: &Key| {
+                    if direction_class {
+                        input.contains_class(key)
+                    } else {
+                        input.contains(key)
+                    }
+                };
+                let position = inputs_rev.position(|input| held(&input)).map(|p| p as u32);
                 // 最後の入力から離しの1F + バッファ分まではタメとして許容
-                let buffer_ok = position.map(|p| p < buffer_frame + 1).unwrap_or(false);
+                let buffer_ok = position.map(|p| p <= buffer_frame).unwrap_or(false);
+                // タメの解除位置をキーの成立位置とする
+                let released = inputs_rev.last_position();
 
                 // 以降該当の入力が hold_frame 分あればOK
                 // 直前に最後1F分を取得しているので じっさいは hold_frame - 1
-                let hold_count = inputs_rev.take_while(|input| input.contains(key)).count() as u32;
-                let hold_ok = hold_count >= hold_frame - 1;
+                let hold_count = skip_held_with_grace(inputs_rev, held, grace_frame);
+                let hold_ok = hold_count >= hold_frame.saturating_sub(1);
 
                 // バッファ内かつタメ時間をクリアしていればOK
-                buffer_ok && hold_ok
+                Some(released).filter(|_| buffer_ok && hold_ok)
             }
-            &CommandKey::On { key } => {
+            CommandKey::On { key } => {
                 // 最後の入力が必要な入力ならOK
                 inputs_rev
                     .next()
-                    .map(|input| input.contains(key))
-                    .unwrap_or(false)
+                    .filter(|input| input.contains(key))
+                    .map(|_| inputs_rev.last_position())
             }
-            &CommandKey::Off { key } => {
-                // 最後の入力が必要な入力を含んでいなければOK
+            CommandKey::Off { key } => {
+                // 最後の入力が必要な入力を全ては含んでいなければOK
+                // 入力を読み切っていたら不成立
                 inputs_rev
                     .next()
-                    .map(|input| input.contains(key) == false)
-                    .unwrap_or(true)
+                    .filter(|input| is_released(*input, key))
+                    .map(|_| inputs_rev.last_position())
             }
+            CommandKey::Mash {
+                key,
+                count,
+                window_frame,
+            } => {
+                log::trace!(
+                    "mash: Key = {:?}, count = {:?}, window = {:?}",
+                    key,
+                    count,
+                    window_frame
+                );
+                // 指定がなければ1回ごとのバッファの合計
+                let window_frame = window_frame
+                    .unwrap_or(config.buffer.saturating_mul(count))
+                    .saturating_add(config.leniency);
+                // 押しの判定を count 回繰り返し，消費したフレーム数がウィンドウ内ならOK
+                // ウィンドウを超えた時点で打ち切る
+                let mut elapsed = 0;
+                let ok = (0..count).all(|_| {
+                    let rest = window_frame.saturating_sub(elapsed) as usize;
+                    let position = inputs_rev
+                        .take(rest)
+                        .position(|input| input.contains(key))
+                        .map(|p| p as u32);
+                    match position {
+                        Some(p) => {
+                            elapsed += p + 1 + skip_held(inputs_rev, key);
+                            elapsed <= window_frame
+                        }
+                        None => false,
+                    }
+                });
+                Some(inputs_rev.last_position()).filter(|_| ok)
+            }
+        });
+
+        // 最後のキーと最初のキーの成立位置の差が入力にかかった時間
+        let mut last = None;
+        let mut first = None;
+        for position in positions {
+            let position = position?;
+            last.get_or_insert(position);
+            first = Some(position);
+        }
+        let duration = match (first, last) {
+            (Some(first), Some(last)) => first - last + 1,
+            _ => 0,
+        };
+
+        // 制限時間を超えていたら不成立
+        if let Some(max_duration) = self.max_duration {
+            log::trace!("duration = {}, max = {}", duration, max_duration);
+            if duration > max_duration.saturating_add(config.leniency) {
+                return None;
+            }
+        }
+
+        Some(MatchResult {
+            duration,
+            max_duration: self.max_duration,
         })
     }
 }
 
-#[cfg(test)]
+// 消費したフレーム数を数えながら逆順に入力を読む
+// 読み戻しは複製しておいた状態に戻すことで行い，読んだ入力を保持しない
+#[derive(Clone)]
+struct ReverseInputs<I: Iterator<Item = Key> + Clone> {
+    inputs: core::iter::Peekable<I>,
+    consumed: u32,
+}
+
+impl<I: Iterator<Item = Key> + Clone> ReverseInputs<I> {
+    fn new(inputs: I) -> Self {
+        ReverseInputs {
+            inputs: inputs.peekable(),
+            consumed: 0,
+        }
+    }
+
+    // 最後に消費した入力の位置 (最新の入力を 0 とする)
+    fn last_position(&self) -> u32 {
+        self.consumed.saturating_sub(1)
+    }
+
+    fn next_if(&mut self, func: impl Fn(&Key) -> bool) -> Option<Key> {
+        let next = self.inputs.next_if(func);
+        if next.is_some() {
+            self.consumed += 1;
+        }
+        next
+    }
+}
+
+impl<I: Iterator<Item = Key> + Clone> Iterator for ReverseInputs<I> {
+    type Item = Key;
+
+    fn next(&mut self) -> Option<Key> {
+        let next = self.inputs.next();
+        if next.is_some() {
+            self.consumed += 1;
+        }
+        next
+    }
+}
+
+// 離しの判定
+// 5 を含むときはスティックがニュートラルに戻り，残りのボタンを全ては押していない
+fn is_released(input: Key, key: Key) -> bool {
+    if key.contains(Key::NEUTRAL) {
+        let buttons = key - Key::NEUTRAL - Key::DIRECTIONS;
+        !input.intersects(Key::DIRECTIONS) && (buttons.is_empty() || !input.contains(buttons))
+    } else {
+        !input.contains(key)
+    }
+}
+
+// key を含む入力を探して位置を返す
+// 複数のキーは simultaneous フレーム前までの入力と合わせて揃えばよい
+fn position_pressed<I>(inputs: &mut ReverseInputs<I>, key: Key, simultaneous: u32) -> Option<u32>
+where
+    I: Iterator<Item = Key> + Clone,
+{
+    let mut position = 0;
+    loop {
+        let input = inputs.next()?;
+        if input.contains(key) {
+            return Some(position);
+        }
+        if simultaneous > 0 && input.intersects(key) {
+            let pressed = inputs
+                .clone()
+                .take(simultaneous as usize)
+                .fold(input, |pressed, input| pressed | input);
+            if pressed.contains(key) {
+                return Some(position);
+            }
+        }
+        position += 1;
+    }
+}
+
+// 押しっぱなしの区間を読み飛ばしてフレーム数を返す
+// 区間の直前の入力は消費しない
+fn skip_held<I>(inputs: &mut ReverseInputs<I>, key: Key) -> u32
+where
+    I: Iterator<Item = Key> + Clone,
+{
+    let mut count = 0;
+    while inputs.next_if(|input| input.contains(key)).is_some() {
+        count += 1;
+    }
+    count
+}
+
+// 途切れを grace_frame まで許容して押しっぱなしの区間を読み飛ばし，押していたフレーム数を返す
+// 途切れたフレームは数えない
+fn skip_held_with_grace<I>(
+    inputs: &mut ReverseInputs<I>,
+    held: impl Fn(&Key) -> bool,
+    grace_frame: u32,
+) -> u32
+where
+    I: Iterator<Item = Key> + Clone,
+{
+    let mut count = 0;
+    loop {
+        if inputs.next_if(&held).is_some() {
+            count += 1;
+            continue;
+        }
+        if grace_frame == 0 {
+            return count;
+        }
+
+        // 途切れた入力を猶予分まで読み進める
+        // 猶予を超えたら読み進めた分は次のキーの判定に使うので戻す
+        let checkpoint = inputs.clone();
+        let mut resumed = false;
+        for _ in 0..=grace_frame {
+            match inputs.next() {
+                Some(input) if held(&input) => {
+                    resumed = true;
+                    break;
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+        if resumed {
+            count += 1;
+        } else {
+            *inputs = checkpoint;
+            return count;
+        }
+    }
+}
+
+// テストは記法の解析を使うので std のときだけ
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
-    const COMMAND: &'static str = "h4(60)[10] > p6[10] > pC[10]";
+    use crate::{config::Socd, script::InputScript};
+
+    fn script(script: &str) -> std::vec::IntoIter<Key> {
+        InputScript::parse(script).unwrap().into_iter()
+    }
+
+    const COMMAND: &str = "h4(60)[10] > p6[10] > pC[10]";
     #[test]
     fn judge_ok() {
         let command = build_command(COMMAND).unwrap();
-        let inputs = (0..10)
-            .map(|_| Key::empty())
-            .chain((0..120).map(|_| Key::BACKWARD))
-            .chain((0..9).map(|_| Key::empty()))
-            .chain((0..10).map(|_| Key::FORWARD))
-            .chain((0..1).map(|_| Key::C))
-            .chain((0..9).map(|_| Key::empty()));
-        assert_eq!(command.judge_inputs(inputs, 10, 10,), true);
+        let inputs = script("5x10 4x120 5x9 6x10 C 5x9");
+        assert!(command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_ok_charge() {
+        // 4 -> 1 -> 4 とタメ方向が変わっても斜めを含めてタメを継続する
+        let command = build_command("c4(60)[10] > p6[10] > pC[10]").unwrap();
+        let inputs = script("5x10 4x30 1x30 4x30 5x9 6x10 C 5x9");
+        assert!(command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_fail_hold_diagonal() {
+        // h は斜めでタメが途切れる
+        let inputs = script("5x10 4x30 1x30 4x30 5x9 6x10 C 5x9");
+        let command = build_command(COMMAND).unwrap();
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+
+        // 逆方向はタメにならない
+        let command = build_command("c4(60)[10] > p6[10] > pC[10]").unwrap();
+        let inputs = script("5x10 4x30 3x30 4x30 5x9 6x10 C 5x9");
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_ok_grace() {
+        // 猶予内の途切れはタメを継続する
+        let command = build_command("h4(60,3)[10] > p6[10] > pC[10]").unwrap();
+        let inputs = script("5x10 4x30 5x3 4x30 5x9 6x10 C 5x9");
+        assert!(command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_fail_grace() {
+        // 猶予を超えた途切れはタメが切れる
+        let command = build_command("h4(60,3)[10] > p6[10] > pC[10]").unwrap();
+        let inputs = script("5x10 4x30 5x4 4x30 5x9 6x10 C 5x9");
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+
+        // 途切れたフレームはタメ時間に数えない
+        let inputs = script("5x10 4x29 5x3 4x30 5x9 6x10 C 5x9");
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_grace_before_push() {
+        // 猶予分読み進めた入力は前のキーの判定に使われる
+        let command = build_command("p2 > h4(20,3)[10] > p6").unwrap();
+        let inputs = script("5x10 2x2 4x20 6");
+        assert!(command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_grace_unread_order() {
+        // 猶予分読み進めた入力は新しい順のまま戻す
+        let command = build_command("pB*2{10} > h3(1,1)").unwrap();
+        assert!(command.judge_inputs(script("B - B 3"), 10, 10));
+    }
+
+    #[test]
+    fn judge_zero_frames() {
+        // 検証エラーになるコマンドでも判定で panic しない
+        let command = Command::new(vec![
+            CommandKey::Hold {
+                key: Key::BACKWARD,
+                buffer_frame: Some(0),
+                hold_frame: Some(0),
+                grace_frame: None,
+                direction_class: false,
+            },
+            CommandKey::Mash {
+                key: Key::A,
+                count: 0,
+                window_frame: None,
+            },
+        ]);
+        assert!(!command.validate().is_empty());
+        let inputs = script("4x10");
+        assert!(command.judge_inputs(inputs.clone(), 0, 0));
+        assert!(command.judge_inputs(inputs, u32::MAX, u32::MAX));
     }
 
     #[test]
     fn judge_fail_hold() {
         let command = build_command(COMMAND).unwrap();
-        let inputs = (0..10)
-            .map(|_| Key::empty())
-            .chain((0..59).map(|_| Key::BACKWARD))
-            .chain((0..1).map(|_| Key::FORWARD))
-            .chain((0..10).map(|_| Key::C));
-        assert_eq!(command.judge_inputs(inputs, 10, 10,), false);
+        let inputs = script("5x10 4x59 6 Cx10");
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+    }
+
+    // 3F 押して gap F 離す連打
+    fn mash_inputs(times: usize, gap: usize) -> std::vec::IntoIter<Key> {
+        script(&vec![format!("Bx3 5x{}", gap); times].join(" "))
+    }
+
+    #[test]
+    fn judge_mash() {
+        let command = build_command("pB*4{30}").unwrap();
+        let inputs = script("5x10").chain(mash_inputs(4, 4));
+        assert!(command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_fail_mash() {
+        let command = build_command("pB*4{30}").unwrap();
+        // 回数不足
+        let inputs = script("5x10").chain(mash_inputs(3, 4));
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+
+        // 4回目がウィンドウ外
+        let inputs = script("5x10").chain(mash_inputs(4, 6));
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_max_duration() {
+        let command = build_command("p2 > p3 > p6 > pA @ 20").unwrap();
+        let inputs = |gap: usize| script(&format!("5x10 2x4 3x{0} 6x{0} Ax2", gap));
+        // 2 から A まで 4 + 5 + 5 + 1 = 15F
+        let result = command.judge(inputs(5), 10, 10).unwrap();
+        assert_eq!(result.duration, 15);
+        assert_eq!(result.max_duration, Some(20));
+
+        // 各キーはバッファ内だが全体で 4 + 9 + 9 + 1 = 23F かかっている
+        assert!(command.judge(inputs(9), 10, 10).is_none());
+        let command = command.with_max_duration(None);
+        assert_eq!(command.judge(inputs(9), 10, 10).unwrap().duration, 23);
+    }
+
+    #[test]
+    fn try_new() {
+        let keys = || core::iter::repeat(CommandKey::On { key: Key::A });
+        let command = Command::try_new(keys().take(Command::MAX_KEYS)).unwrap();
+        assert_eq!(command.keys().count(), Command::MAX_KEYS);
+        match Command::try_new(keys().take(Command::MAX_KEYS + 2)) {
+            Err(Error::Parse(ParseError::TooManyKeys { len, max })) => {
+                assert_eq!((len, max), (Command::MAX_KEYS + 2, Command::MAX_KEYS))
+            }
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn judge_inputs_single_pass() {
+        // 複製できないイテレータでも判定できる
+        struct SinglePass(std::vec::IntoIter<Key>);
+        impl Iterator for SinglePass {
+            type Item = Key;
+            fn next(&mut self) -> Option<Key> {
+                self.0.next()
+            }
+        }
+        impl DoubleEndedIterator for SinglePass {
+            fn next_back(&mut self) -> Option<Key> {
+                self.0.next_back()
+            }
+        }
+
+        let command = build_command("c4(10,2)[10] > p6").unwrap();
+        let inputs = SinglePass(script("4x5 5 4x5 6"));
+        assert!(command.judge_inputs(inputs, 10, 10));
+    }
+
+    #[test]
+    fn judge_adjacent_frames() {
+        // 押しっぱなしの区間の直前のフレームは読み飛ばさず，前のキーの判定に使う
+        let command = build_command("p2 > p6").unwrap();
+        assert!(command.judge_inputs(script("5 2 6"), 10, 10));
+        assert!(command.judge_inputs(script("5 2 6x3"), 10, 10));
+        let command = build_command("h4(3) > p6 > pA").unwrap();
+        assert!(command.judge_inputs(script("5 4x3 6 A"), 10, 10));
+        // 区間の直前のフレームもバッファに数える
+        let command = build_command("p2[3] > p6").unwrap();
+        assert!(command.judge_inputs(script("2 5x2 6x2"), 10, 10));
+        assert!(!command.judge_inputs(script("2 5x3 6x2"), 10, 10));
+    }
+
+    #[test]
+    fn judge_dash() {
+        // ニュートラル1F を挟んだ2回入力
+        let command = build_command("dash6[10]").unwrap();
+        let inputs = script("5x10 6x3 5 6x2");
+        assert!(command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_dash_needs_neutral() {
+        // 斜めや反対方向を挟んでもダッシュにならない
+        let command = build_command("dash6[10]").unwrap();
+        assert!(!command.judge_inputs(script("5x10 6x3 3 6x2"), 10, 10));
+        assert!(!command.judge_inputs(script("5x10 6x3 4 6x2"), 10, 10));
+        // ボタンを押していてもニュートラルならよい
+        assert!(command.judge_inputs(script("5x10 6x3 A 6x2"), 10, 10));
+        let command = build_command("tapA > pB").unwrap();
+        assert!(command.judge_inputs(script("A 6 B"), 10, 10));
+    }
+
+    #[test]
+    fn judge_off() {
+        // 同時押しの離しはどれかを離せばよい
+        let command = build_command("pA > fAB").unwrap();
+        assert!(command.judge_inputs(script("5 A B"), 10, 10));
+        assert!(!command.judge_inputs(script("5 A AB"), 10, 10));
+        // f5 はスティックがニュートラルに戻ること
+        let command = build_command("p6 > f5").unwrap();
+        assert!(command.judge_inputs(script("5 6 5"), 10, 10));
+        assert!(command.judge_inputs(script("5 6 A"), 10, 10));
+        assert!(!command.judge_inputs(script("5 6 3"), 10, 10));
+        let command = build_command("p6A > f5A").unwrap();
+        assert!(command.judge_inputs(script("5 6A 5"), 10, 10));
+        assert!(!command.judge_inputs(script("5 6A A"), 10, 10));
+    }
+
+    #[test]
+    fn judge_fail_dash() {
+        // 押しっぱなしはダッシュにならない
+        let command = build_command("dash6[10]").unwrap();
+        let inputs = script("5x10 6x6");
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+
+        // 1回目の入力がバッファ外
+        let inputs = script("5x10 6x3 5x10 6x2");
+        assert!(!command.judge_inputs(inputs, 10, 10,));
+    }
+
+    #[test]
+    fn judge_simultaneous() {
+        // 1F ずれた同時押し
+        let command = build_command("p2 > pAB").unwrap();
+        let lenient = JudgeConfig {
+            simultaneous: 1,
+            ..JudgeConfig::default()
+        };
+        let judge = |inputs, config: JudgeConfig| {
+            command
+                .judge_with_config(script(inputs), &config, None)
+                .is_some()
+        };
+        assert!(!judge("5 2 A B", JudgeConfig::default()));
+        assert!(judge("5 2 A B", lenient));
+        assert!(judge("5 2 B A", lenient));
+        // 2F ずれると同時押しではない
+        assert!(!judge("5 2 A - B", lenient));
+    }
+
+    #[test]
+    fn judge_negative_edge() {
+        // A を押したまま 236 を入力して離す
+        let command = build_command("p2 > p3 > p6 > pA").unwrap();
+        let config = JudgeConfig {
+            negative_edge: true,
+            ..JudgeConfig::default()
+        };
+        let inputs = "Ax20 2A 3A 6A -";
+        assert!(!command.judge_inputs(script(inputs), 10, 10));
+        let result = command.judge_with_config(script(inputs), &config, None);
+        assert_eq!(result.map(|result| result.duration), Some(4));
+
+        // 方向の押しは離しで成立しない
+        let command = build_command("p2 > p6").unwrap();
+        let inputs = "2 6x20 -";
+        assert!(command
+            .judge_with_config(script(inputs), &config, None)
+            .is_none());
+    }
+
+    #[test]
+    fn judge_leniency() {
+        let command = build_command("p2 > p6[3] @ 5").unwrap();
+        let config = JudgeConfig {
+            leniency: 2,
+            ..JudgeConfig::default()
+        };
+        // 全体で 6F，2 の押しから 6 の押しまで 4F
+        let inputs = "5 2 -x4 6";
+        assert!(!command.judge_inputs(script(inputs), 10, 10));
+        assert!(command
+            .judge_with_config(script(inputs), &config, None)
+            .is_some());
+        assert!(command
+            .judge_with_config(script("5 2 -x6 6"), &config, None)
+            .is_none());
+    }
+
+    #[test]
+    fn judge_socd() {
+        let command = build_command("p2 > p6").unwrap();
+        let config = JudgeConfig {
+            socd: Socd::Neutral,
+            ..JudgeConfig::default()
+        };
+        let inputs = "5 2 46";
+        assert!(command.judge_inputs(script(inputs), 10, 10));
+        assert!(command
+            .judge_with_config(script(inputs), &config, None)
+            .is_none());
     }
 }
@@ -0,0 +1,208 @@
+// 判定の既定値と入力の扱い
+// 技一覧ごとに決めて Matcher に渡し，技ごとに上書きできる
+use crate::types::button::Key;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(default))]
+pub struct JudgeConfig {
+    // キー入力ごとのバッファの既定値
+    pub buffer: u32,
+    // タメ時間の既定値
+    pub hold: u32,
+    // 複数のキーの同時押しとみなす入力のずれ (0 なら同じフレームのみ)
+    pub simultaneous: u32,
+    // バッファと制限時間に足す猶予
+    pub leniency: u32,
+    // ボタンの押しをボタンの離しでも成立させる
+    pub negative_edge: bool,
+    pub socd: Socd,
+}
+
+impl JudgeConfig {
+    pub fn new(buffer: u32, hold: u32) -> Self {
+        JudgeConfig {
+            buffer,
+            hold,
+            ..JudgeConfig::default()
+        }
+    }
+
+    // 猶予を含めたバッファ
+    pub(crate) fn buffer_frame(&self, buffer_frame: Option<u32>) -> u32 {
+        buffer_frame
+            .unwrap_or(self.buffer)
+            .saturating_add(self.leniency)
+    }
+}
+
+impl Default for JudgeConfig {
+    fn default() -> Self {
+        JudgeConfig {
+            buffer: 10,
+            hold: 10,
+            simultaneous: 0,
+            leniency: 0,
+            negative_edge: false,
+            socd: Socd::Raw,
+        }
+    }
+}
+
+// 左右または上下の同時入力 (Simultaneous Opposite Cardinal Directions) の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum Socd {
+    // そのまま判定する
+    #[default]
+    Raw,
+    // 左右，上下どちらもニュートラルにする
+    Neutral,
+    // 左右はニュートラル，上下は上を優先する
+    UpPriority,
+}
+
+impl Socd {
+    pub fn resolve(self, input: Key) -> Key {
+        if self == Socd::Raw {
+            return input;
+        }
+        let directions = input - Key::BUTTONS - Key::NEUTRAL;
+        let mut resolved = directions;
+        if resolved.intersects(Key::FORWARD | Key::FU | Key::FD)
+            && resolved.intersects(Key::BACKWARD | Key::BU | Key::BD)
+        {
+            resolved = strip(resolved, Key::FORWARD | Key::BACKWARD);
+        }
+        if resolved.intersects(Key::UP | Key::FU | Key::BU)
+            && resolved.intersects(Key::DOWN | Key::FD | Key::BD)
+        {
+            resolved = match self {
+                Socd::UpPriority => strip(resolved, Key::DOWN) | Key::UP,
+                _ => strip(resolved, Key::UP | Key::DOWN),
+            };
+        }
+        if resolved == directions {
+            return input;
+        }
+        let resolved = combine(resolved);
+        let mut output = (input & Key::BUTTONS) | resolved;
+        if resolved.is_empty() {
+            output |= Key::NEUTRAL;
+        }
+        output
+    }
+}
+
+// 斜めと，それを構成する左右と上下
+const DIAGONALS: [(Key, Key, Key); 4] = [
+    (Key::FU, Key::FORWARD, Key::UP),
+    (Key::FD, Key::FORWARD, Key::DOWN),
+    (Key::BU, Key::BACKWARD, Key::UP),
+    (Key::BD, Key::BACKWARD, Key::DOWN),
+];
+
+// 方向成分を取り除く
+// 斜めは残りの成分の方向になる
+fn strip(key: Key, remove: Key) -> Key {
+    let mut stripped = key - remove;
+    for &(diagonal, horizontal, vertical) in DIAGONALS.iter() {
+        if !key.contains(diagonal) {
+            continue;
+        }
+        if remove.contains(horizontal) {
+            stripped = (stripped - diagonal) | (vertical - remove);
+        } else if remove.contains(vertical) {
+            stripped = (stripped - diagonal) | (horizontal - remove);
+        }
+    }
+    stripped
+}
+
+// 左右と上下の組み合わせを斜めにまとめる
+fn combine(key: Key) -> Key {
+    let mut combined = key;
+    for &(diagonal, horizontal, vertical) in DIAGONALS.iter() {
+        if combined.contains(horizontal | vertical) {
+            combined = (combined - horizontal - vertical) | diagonal;
+        }
+    }
+    combined
+}
+
+// 技ごとの上書き
+// SOCD は入力履歴に反映されるので上書きできない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(default))]
+pub struct JudgeOverride {
+    pub buffer: Option<u32>,
+    pub hold: Option<u32>,
+    pub simultaneous: Option<u32>,
+    pub leniency: Option<u32>,
+    pub negative_edge: Option<bool>,
+}
+
+impl JudgeOverride {
+    pub fn apply(&self, config: &JudgeConfig) -> JudgeConfig {
+        JudgeConfig {
+            buffer: self.buffer.unwrap_or(config.buffer),
+            hold: self.hold.unwrap_or(config.hold),
+            simultaneous: self.simultaneous.unwrap_or(config.simultaneous),
+            leniency: self.leniency.unwrap_or(config.leniency),
+            negative_edge: self.negative_edge.unwrap_or(config.negative_edge),
+            socd: config.socd,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == JudgeOverride::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn socd() {
+        let left_right = Key::FORWARD | Key::BACKWARD;
+        assert_eq!(Socd::Raw.resolve(left_right), left_right);
+        assert_eq!(
+            Socd::Neutral.resolve(left_right | Key::A),
+            Key::NEUTRAL | Key::A
+        );
+        assert_eq!(Socd::Neutral.resolve(Key::FD | Key::BACKWARD), Key::DOWN);
+        assert_eq!(Socd::Neutral.resolve(Key::UP | Key::DOWN), Key::NEUTRAL);
+        assert_eq!(Socd::UpPriority.resolve(Key::UP | Key::DOWN), Key::UP);
+        assert_eq!(Socd::UpPriority.resolve(Key::FD | Key::UP), Key::FU);
+        // 同時入力がなければそのまま
+        assert_eq!(Socd::Neutral.resolve(Key::FD | Key::C), Key::FD | Key::C);
+    }
+
+    #[test]
+    fn apply_override() {
+        let config = JudgeConfig {
+            negative_edge: true,
+            socd: Socd::Neutral,
+            ..JudgeConfig::new(8, 30)
+        };
+        let config = JudgeOverride {
+            buffer: Some(12),
+            negative_edge: Some(false),
+            ..JudgeOverride::default()
+        }
+        .apply(&config);
+        assert_eq!(
+            config,
+            JudgeConfig {
+                buffer: 12,
+                hold: 30,
+                simultaneous: 0,
+                leniency: 0,
+                negative_edge: false,
+                socd: Socd::Neutral,
+            }
+        );
+    }
+}
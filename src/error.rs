@@ -1,13 +1,176 @@
-use failure::Fail;
+use crate::{builder::BuildError, validate::Diagnostic};
+use std::{fmt, num::ParseIntError};
 
-#[derive(Debug, Fail)]
+// 解析，検証，リソースの読み書き，CommandBuilder で分けて返す
+#[derive(Debug)]
 pub enum Error {
-    #[fail(display = "unknown key name")]
-    UnKnownKeyName,
-    #[fail(display = "parse int error: {:?}", err)]
-    IntParseError { err: std::num::ParseIntError },
-    #[fail(display = "nom parse error: {}", string)]
-    NomParseError { string: String },
-    #[fail(display = "not complete parse: {}", rest)]
-    NotCompleteParse { rest: String },
+    Parse(ParseError),
+    Validation(Diagnostic),
+    Resource(ResourceError),
+    Build(BuildError),
+}
+
+// 記法や断片の解析エラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnknownKeyName,
+    InvalidNumber { err: ParseIntError },
+    Syntax { string: String },
+    NotComplete { rest: String },
+    TooManyKeys { len: usize, max: usize },
+    EmptyKey { index: usize },
+    UndefinedMacro { name: String },
+    RecursiveMacro { name: String, chain: String },
+    InvalidMacro { name: String, reason: String },
+}
+
+// 技一覧や入力ログなどの読み書きのエラー
+#[derive(Debug)]
+pub enum ResourceError {
+    Ron(ron::de::Error),
+    RonSerialize(ron::ser::Error),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    InvalidMove {
+        name: String,
+        source: Box<Error>,
+    },
+    DuplicateMove {
+        name: String,
+    },
+    InvalidInputLog {
+        position: usize,
+        reason: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(err) => err.fmt(f),
+            Error::Validation(diagnostic) => write!(f, "invalid command: {}", diagnostic),
+            Error::Resource(err) => err.fmt(f),
+            Error::Build(err) => err.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnknownKeyName => write!(f, "unknown key name"),
+            ParseError::InvalidNumber { err } => write!(f, "parse int error: {:?}", err),
+            ParseError::Syntax { string } => write!(f, "nom parse error: {}", string),
+            ParseError::NotComplete { rest } => write!(f, "not complete parse: {}", rest),
+            ParseError::TooManyKeys { len, max } => {
+                write!(f, "too many keys: {} (max {})", len, max)
+            }
+            ParseError::EmptyKey { index } => write!(f, "empty key at step {}", index),
+            ParseError::UndefinedMacro { name } => write!(f, "undefined macro: ${}", name),
+            ParseError::RecursiveMacro { name, chain } => {
+                write!(f, "recursive macro ${}: {}", name, chain)
+            }
+            ParseError::InvalidMacro { name, reason } => {
+                write!(f, "invalid macro ${}: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResourceError::Ron(err) => write!(f, "ron error: {}", err),
+            ResourceError::RonSerialize(err) => write!(f, "ron error: {}", err),
+            #[cfg(feature = "json")]
+            ResourceError::Json(err) => write!(f, "json error: {}", err),
+            ResourceError::InvalidMove { name, source } => {
+                write!(f, "invalid move \"{}\": {}", name, source)
+            }
+            ResourceError::DuplicateMove { name } => write!(f, "duplicate move name: {}", name),
+            ResourceError::InvalidInputLog { position, reason } => {
+                write!(f, "invalid input log at {}: {}", position, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(err) => Some(err),
+            Error::Validation(diagnostic) => Some(diagnostic),
+            Error::Resource(err) => Some(err),
+            Error::Build(err) => Some(err),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::InvalidNumber { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResourceError::Ron(err) => Some(err),
+            ResourceError::RonSerialize(err) => Some(err),
+            #[cfg(feature = "json")]
+            ResourceError::Json(err) => Some(err),
+            ResourceError::InvalidMove { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+impl std::error::Error for BuildError {}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<Diagnostic> for Error {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Error::Validation(diagnostic)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(err: BuildError) -> Self {
+        Error::Build(err)
+    }
+}
+
+impl From<ResourceError> for Error {
+    fn from(err: ResourceError) -> Self {
+        Error::Resource(err)
+    }
+}
+
+impl From<ron::de::Error> for Error {
+    fn from(err: ron::de::Error) -> Self {
+        Error::Resource(ResourceError::Ron(err))
+    }
+}
+
+impl From<ron::ser::Error> for Error {
+    fn from(err: ron::ser::Error) -> Self {
+        Error::Resource(ResourceError::RonSerialize(err))
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Resource(ResourceError::Json(err))
+    }
 }
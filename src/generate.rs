@@ -0,0 +1,407 @@
+// コマンドを成立させるフレームごとの入力を作る
+// judge の逆で，各キー入力の間隔をバッファ内で選ぶ
+use crate::{
+    command::Command,
+    types::{button::Key, input::CommandKey},
+};
+use alloc::{vec, vec::Vec};
+
+// 入力間隔の選び方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    // できるだけ詰める
+    Fastest,
+    // バッファいっぱいまで空ける
+    Slowest,
+    // バッファ内でランダム (同じ seed なら同じ入力)
+    Random { seed: u64 },
+}
+
+// 1つのキー入力に対応する入力と，その後に空けられるフレーム数
+struct Step {
+    body: Vec<Key>,
+    gap_min: u32,
+    gap_max: u32,
+    // 成立位置が入力の最後のフレームか (false なら最初のフレーム)
+    anchor_end: bool,
+}
+
+pub(crate) fn generate(
+    command: &Command,
+    timing: Timing,
+    default_buffer: u32,
+    default_hold: u32,
+) -> Option<Vec<Key>> {
+    let filler = Key::empty();
+    let keys = command.keys().collect::<Vec<_>>();
+
+    let mut steps = vec![];
+    for key in &keys {
+        let step = match **key {
+            CommandKey::Push { key, buffer_frame } => Step {
+                body: vec![key],
+                gap_min: 0,
+                gap_max: buffer_frame.unwrap_or(default_buffer).checked_sub(1)?,
+                anchor_end: false,
+            },
+            CommandKey::Release { key, buffer_frame } => Step {
+                body: vec![key],
+                gap_min: 1,
+                gap_max: buffer_frame.unwrap_or(default_buffer),
+                anchor_end: true,
+            },
+            CommandKey::Hold {
+                key,
+                buffer_frame,
+                hold_frame,
+                ..
+            } => Step {
+                body: vec![key; hold_frame.unwrap_or(default_hold).max(1) as usize],
+                gap_min: 0,
+                gap_max: buffer_frame.unwrap_or(default_buffer),
+                anchor_end: true,
+            },
+            CommandKey::On { key } => Step {
+                body: vec![key],
+                gap_min: 0,
+                gap_max: 0,
+                anchor_end: true,
+            },
+            CommandKey::Off { .. } => Step {
+                body: vec![filler],
+                gap_min: 0,
+                gap_max: 0,
+                anchor_end: true,
+            },
+            CommandKey::Mash {
+                key,
+                count,
+                window_frame,
+            } => {
+                // 1F 押して 1F 離すのを繰り返す
+                let window_frame =
+                    window_frame.unwrap_or(default_buffer.saturating_mul(count)) as u64;
+                let body = (0..count)
+                    .flat_map(|i| if i == 0 { vec![key] } else { vec![filler, key] })
+                    .collect::<Vec<_>>();
+                Step {
+                    gap_max: window_frame.checked_sub(body.len() as u64)? as u32,
+                    body,
+                    gap_min: 0,
+                    anchor_end: false,
+                }
+            }
+        };
+        steps.push(step);
+    }
+
+    // 次のキーが押しっぱなしとして前の入力まで読み進めないように空ける
+    for i in 1..steps.len() {
+        let last = *steps[i - 1].body.last()?;
+        let separation = match *keys[i] {
+            CommandKey::Push { key, .. } | CommandKey::Mash { key, .. } if last.contains(key) => 1,
+            CommandKey::Hold {
+                key,
+                grace_frame,
+                direction_class,
+                ..
+            } => {
+                let held = if direction_class {
+                    last.contains_class(key)
+                } else {
+                    last.contains(key)
+                };
+                if held {
+                    grace_frame.unwrap_or(0) + 1
+                } else {
+                    0
+                }
+            }
+            _ => 0,
+        };
+        let step = &mut steps[i - 1];
+        step.gap_min = step.gap_min.max(separation);
+    }
+    if steps.iter().any(|step| step.gap_min > step.gap_max) {
+        return None;
+    }
+
+    let mut rng = match timing {
+        Timing::Random { seed } => Some(XorShift::new(seed)),
+        _ => None,
+    };
+    let mut gaps = steps
+        .iter()
+        .map(|step| match (timing, &mut rng) {
+            (Timing::Slowest, _) => step.gap_max,
+            (_, Some(rng)) => rng.range(step.gap_min, step.gap_max),
+            _ => step.gap_min,
+        })
+        .collect::<Vec<_>>();
+
+    loop {
+        // 全体の制限時間に収まるよう間隔を詰める
+        if let Some(max_duration) = command.max_duration() {
+            let mut excess = duration(&steps, &gaps).saturating_sub(max_duration);
+            let last = steps.len().saturating_sub(1);
+            for (step, gap) in steps.iter().zip(gaps.iter_mut()).take(last) {
+                let shrink = excess.min(*gap - step.gap_min);
+                *gap -= shrink;
+                excess -= shrink;
+            }
+            if excess > 0 {
+                return None;
+            }
+        }
+
+        // 猶予つきのタメは途切れを読み飛ばすので，猶予内に前の入力が入らないよう空ける
+        // 空けた分は詰められないようにして制限時間を確認し直す
+        match grace_overlap(&steps, &gaps, &keys) {
+            Some((i, extra)) => {
+                let step = (0..i)
+                    .rev()
+                    .find(|&j| steps[j].gap_max - gaps[j] >= extra)?;
+                gaps[step] += extra;
+                steps[step].gap_min = gaps[step];
+            }
+            None => break,
+        }
+    }
+
+    let mut inputs = vec![];
+    for (step, gap) in steps.iter().zip(&gaps) {
+        inputs.extend_from_slice(&step.body);
+        inputs.extend((0..*gap).map(|_| filler));
+    }
+    Some(inputs)
+}
+
+// 最初のキーの成立位置から最後のキーの成立位置までのフレーム数
+fn duration(steps: &[Step], gaps: &[u32]) -> u32 {
+    let mut anchors = vec![];
+    let mut position = 0;
+    for (step, gap) in steps.iter().zip(gaps) {
+        let len = step.body.len() as u32;
+        anchors.push(position + if step.anchor_end { len - 1 } else { 0 });
+        position += len + gap;
+    }
+    match (anchors.first(), anchors.last()) {
+        (Some(first), Some(last)) => last - first + 1,
+        _ => 0,
+    }
+}
+
+// タメの直前の猶予フレーム内に同じキーの入力があれば，
+// そのタメの番号と足りないフレーム数を返す
+fn grace_overlap(steps: &[Step], gaps: &[u32], keys: &[&CommandKey]) -> Option<(usize, u32)> {
+    for (i, key) in keys.iter().enumerate() {
+        let (key, grace_frame, direction_class) = match **key {
+            CommandKey::Hold {
+                key,
+                grace_frame: Some(grace_frame),
+                direction_class,
+                ..
+            } if grace_frame > 0 => (key, grace_frame, direction_class),
+            _ => continue,
+        };
+
+        // タメより前の入力を新しい順に見る
+        let before = steps[..i].iter().zip(gaps).rev().flat_map(|(step, &gap)| {
+            (0..gap)
+                .map(|_| Key::empty())
+                .chain(step.body.iter().rev().copied())
+        });
+        let mut interrupted = 0;
+        for input in before {
+            let held = if direction_class {
+                input.contains_class(key)
+            } else {
+                input.contains(key)
+            };
+            if held {
+                return Some((i, grace_frame + 1 - interrupted));
+            }
+            interrupted += 1;
+            if interrupted > grace_frame {
+                break;
+            }
+        }
+    }
+    None
+}
+
+// 依存を増やさないための乱数
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // 0 だと同じ値しか出ないので混ぜる
+        XorShift(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn range(&mut self, min: u32, max: u32) -> u32 {
+        min + (self.next() % (max as u64 - min as u64 + 1)) as u32
+    }
+}
+
+// テストは記法の解析を使うので std のときだけ
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{script::InputScript, types::build_command};
+    use proptest::prelude::*;
+
+    fn generate_script(command: &str, timing: Timing) -> Option<String> {
+        let command = build_command(command).unwrap();
+        generate(&command, timing, 10, 10).map(|inputs| InputScript::from(inputs).to_string())
+    }
+
+    #[test]
+    fn generate_fastest() {
+        assert_eq!(
+            generate_script("p2 > p3 > p6 > pA", Timing::Fastest).unwrap(),
+            "2 3 6 A"
+        );
+        assert_eq!(
+            generate_script("h4(5)[10] > p6 > pC", Timing::Fastest).unwrap(),
+            "4x5 6 C"
+        );
+        assert_eq!(
+            generate_script("dash6[10]", Timing::Fastest).unwrap(),
+            "6 - 6"
+        );
+        assert_eq!(
+            generate_script("pB*3{10} > r6", Timing::Fastest).unwrap(),
+            "B - B - B 6 -"
+        );
+    }
+
+    #[test]
+    fn generate_slowest() {
+        assert_eq!(
+            generate_script("p2[5] > p6[3] > pA[4]", Timing::Slowest).unwrap(),
+            "2 -x4 6 -x2 A -x3"
+        );
+        // 制限時間に収まるように詰める
+        assert_eq!(
+            generate_script("p2[5] > p6[3] > pA[4] @ 5", Timing::Slowest).unwrap(),
+            "2 6 -x2 A -x3"
+        );
+        assert_eq!(generate_script("p2 > p6 > pA @ 2", Timing::Slowest), None);
+    }
+
+    #[test]
+    fn generate_random() {
+        let a = generate_script("p2 > p3 > p6 > pA", Timing::Random { seed: 1 });
+        let b = generate_script("p2 > p3 > p6 > pA", Timing::Random { seed: 1 });
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn generate_grace() {
+        // 猶予で読み飛ばされないよう前の入力から離す
+        assert_eq!(
+            generate_script("pA*1{10} > f2 > hA(1,1)", Timing::Fastest).unwrap(),
+            "A -x2 A"
+        );
+    }
+
+    #[test]
+    fn generate_infeasible() {
+        // 押しっぱなしの直後に同じキーを押すことはできない
+        assert_eq!(generate_script("n6 > p6", Timing::Fastest), None);
+    }
+
+    fn step() -> impl Strategy<Value = String> {
+        prop::sample::select(vec!["2", "3", "4", "6", "8", "1", "A", "B", "6A", "2C"])
+            .prop_flat_map(step_of)
+    }
+
+    fn step_of(key: &'static str) -> impl Strategy<Value = String> {
+        let frame = 1..20u32;
+        prop_oneof![
+            frame.clone().prop_map(move |b| format!("p{}[{}]", key, b)),
+            Just(format!("r{}", key)),
+            (frame.clone(), 0..4u32).prop_map(move |(h, g)| format!("h{}({},{})", key, h, g)),
+            frame.prop_map(move |h| format!("c{}({})", key, h)),
+            Just(format!("n{}", key)),
+            Just(format!("f{}", key)),
+            (1..5u32, 10..40u32).prop_map(move |(c, w)| format!("p{}*{}{{{}}}", key, c, w)),
+        ]
+    }
+
+    // 制限時間がなく，キー同士が重ならないので必ず作れるコマンド
+    fn feasible_command() -> impl Strategy<Value = String> {
+        Just(vec!["2", "4", "6", "8", "A", "B", "C"])
+            .prop_shuffle()
+            .prop_flat_map(|keys| {
+                (1..6usize).prop_flat_map(move |len| {
+                    keys[..len]
+                        .iter()
+                        .map(|&key| step_of(key))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .prop_map(|steps| steps.join(" > "))
+    }
+
+    fn command() -> impl Strategy<Value = String> {
+        (
+            prop::collection::vec(step(), 1..6),
+            prop::option::of(1..80u32),
+        )
+            .prop_map(|(steps, duration)| {
+                let mut command = steps.join(" > ");
+                if let Some(duration) = duration {
+                    command += &format!(" @ {}", duration);
+                }
+                command
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn feasible_commands_are_generated(command in feasible_command(), seed in any::<u64>()) {
+            let command = build_command(&command).unwrap();
+            for &timing in &[Timing::Fastest, Timing::Slowest, Timing::Random { seed }] {
+                let inputs = generate(&command, timing, 10, 10);
+                prop_assert!(inputs.is_some(), "{:?}", timing);
+                let inputs = inputs.unwrap();
+                prop_assert!(
+                    command.judge_inputs(inputs.iter().copied(), 10, 10),
+                    "{:?}: {}",
+                    timing,
+                    InputScript::from(inputs)
+                );
+            }
+        }
+
+        #[test]
+        fn generated_inputs_are_accepted(command in command(), seed in any::<u64>()) {
+            let command = build_command(&command).unwrap();
+            // 最短で作れるなら，どの間隔の選び方でも制限時間に収まる
+            let feasible = generate(&command, Timing::Fastest, 10, 10).is_some();
+            for &timing in &[Timing::Fastest, Timing::Slowest, Timing::Random { seed }] {
+                let inputs = generate(&command, timing, 10, 10);
+                prop_assert_eq!(inputs.is_some(), feasible, "{:?}", timing);
+                if let Some(inputs) = inputs {
+                    prop_assert!(
+                        command.judge_inputs(inputs.iter().copied(), 10, 10),
+                        "{:?}: {}",
+                        timing,
+                        InputScript::from(inputs)
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,17 +1,78 @@
+// 判定の中核 (Key, CommandKey, Command, 判定) は no_std で使える
+// 記法の解析とリソース形式は std，入力履歴を持つ Matcher などは alloc が必要
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+mod builder;
+mod charge;
 mod command;
+mod config;
+#[cfg(feature = "std")]
 mod error;
+#[cfg(feature = "alloc")]
+mod generate;
+#[cfg(feature = "alloc")]
+mod matcher;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
 pub mod resource;
+#[cfg(feature = "std")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod syntax;
+pub mod system;
 pub(crate) mod types;
+mod validate;
 
+#[cfg(feature = "alloc")]
 pub use crate::{
-    command::Command,
+    builder::{BuildError, CommandBuilder, Motion},
+    generate::Timing,
+    matcher::{Matcher, MatcherState},
+};
+pub use crate::{
+    charge::ChargeTracker,
+    command::{Command, MatchResult},
+    config::{JudgeConfig, JudgeOverride, Socd},
     types::{button::Key, input::CommandKey},
+    validate::{Diagnostic, Lint, Severity},
+};
+#[cfg(feature = "std")]
+pub use crate::{
+    error::{Error, ParseError, ResourceError},
+    resource::move_list::{Category, Move, MoveList},
+    script::InputScript,
+    syntax::{format_command, FormatStyle},
+    types::Macros,
 };
 
-pub fn deserialize(data: &str) -> Result<Command, failure::Error> {
-    Ok(types::build_command(data)?)
+#[cfg(feature = "std")]
+pub fn deserialize(data: &str) -> Result<Command, Error> {
+    types::build_command(data)
 }
 
-pub fn serialize(command: &Command) -> Result<String, failure::Error> {
+#[cfg(feature = "std")]
+pub fn serialize(command: &Command) -> Result<String, Error> {
     Ok(ron::ser::to_string(command)?)
 }
+
+// RON の構造化した形式か記法の文字列から作る
+#[cfg(feature = "std")]
+pub fn deserialize_structured(data: &str) -> Result<Command, Error> {
+    let mut de = ron::de::Deserializer::from_str(data)?;
+    let command = resource::structured::command::deserialize(&mut de)?;
+    de.end()?;
+    Ok(command)
+}
+
+// 構造化した形式で RON にする
+#[cfg(feature = "std")]
+pub fn serialize_structured(command: &Command) -> Result<String, Error> {
+    let mut ser = ron::ser::Serializer::new(None, false);
+    resource::structured::command::serialize(command, &mut ser)?;
+    Ok(ser.into_output_string())
+}
@@ -0,0 +1,376 @@
+use crate::{
+    charge::ChargeTracker,
+    command::{Command, MatchResult},
+    config::JudgeConfig,
+    types::button::Key,
+};
+use alloc::{collections::VecDeque, vec::Vec};
+
+// フレームごとの入力を保持してコマンドを判定する
+// 履歴は生成時に確保するので，update と judge ではヒープを確保しない
+// 巻き戻しには save_state と load_state を使う
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    // 古い順の入力履歴
+    history: VecDeque<Key>,
+    capacity: usize,
+    charge: ChargeTracker,
+    config: JudgeConfig,
+}
+
+impl Matcher {
+    pub fn new(capacity: usize, default_buffer: u32, default_hold: u32) -> Self {
+        Matcher::from_config(capacity, JudgeConfig::new(default_buffer, default_hold))
+    }
+
+    pub fn from_config(capacity: usize, config: JudgeConfig) -> Self {
+        Matcher {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            charge: ChargeTracker::default(),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &JudgeConfig {
+        &self.config
+    }
+
+    // 1F 分の入力を追加
+    // 保持数を超えたら古いものから捨てる
+    // SOCD は履歴に入れる前に解決する
+    pub fn update(&mut self, input: Key) {
+        let input = self.config.socd.resolve(input);
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(input);
+        self.charge.update(input);
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.charge.clear();
+    }
+
+    // タメを離したあと維持するフレーム数を設定
+    pub fn with_charge_retain(mut self, retain_frame: u32) -> Self {
+        self.charge = ChargeTracker::new(retain_frame);
+        self
+    }
+
+    pub fn charge(&self) -> &ChargeTracker {
+        &self.charge
+    }
+
+    pub fn inputs(&self) -> impl DoubleEndedIterator<Item = Key> + Clone + '_ {
+        self.history.iter().copied()
+    }
+
+    pub fn judge(&self, command: &Command) -> Option<MatchResult> {
+        self.judge_with_config(command, &self.config)
+    }
+
+    // 技ごとに上書きした設定で判定する
+    pub fn judge_with_config(
+        &self,
+        command: &Command,
+        config: &JudgeConfig,
+    ) -> Option<MatchResult> {
+        command.judge_with_config(self.inputs(), config, Some(&self.charge))
+    }
+
+    // 巻き戻しのために現在の状態を保存する
+    // 保持数分を確保しておくので，save_state_into で使い回せばヒープを確保しない
+    pub fn save_state(&self) -> MatcherState {
+        let mut state = MatcherState {
+            history: Vec::with_capacity(self.capacity),
+            charge: self.charge,
+        };
+        self.save_state_into(&mut state);
+        state
+    }
+
+    pub fn save_state_into(&self, state: &mut MatcherState) {
+        state.history.clear();
+        state.history.extend(self.history.iter().copied());
+        state.charge = self.charge;
+    }
+
+    // 保存した状態に戻す
+    // 保持数を超える履歴は古いものから捨てる
+    pub fn load_state(&mut self, state: &MatcherState) {
+        let skip = state.history.len().saturating_sub(self.capacity);
+        self.history.clear();
+        self.history.extend(state.history[skip..].iter().copied());
+        self.charge = state.charge;
+    }
+
+    // 成立したコマンドの番号と結果を列挙
+    pub fn judge_all<'a>(
+        &'a self,
+        commands: impl IntoIterator<Item = &'a Command> + 'a,
+    ) -> impl Iterator<Item = (usize, MatchResult)> + 'a {
+        commands
+            .into_iter()
+            .enumerate()
+            .filter_map(move |(i, command)| self.judge(command).map(|result| (i, result)))
+    }
+}
+
+// 巻き戻しで保存する Matcher の状態
+// 保持数と判定設定は Matcher 側に残る
+// Key の serde 実装は std のときだけ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatcherState {
+    // 古い順の入力履歴
+    history: Vec<Key>,
+    charge: ChargeTracker,
+}
+
+impl MatcherState {
+    pub fn inputs(&self) -> &[Key] {
+        &self.history
+    }
+
+    pub fn charge(&self) -> &ChargeTracker {
+        &self.charge
+    }
+}
+
+// テストは記法の解析を使うので std のときだけ
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::types::build_command;
+
+    #[test]
+    fn streaming() {
+        let commands = vec![
+            build_command("p2 > p3 > p6 > pA @ 20").unwrap(),
+            build_command("p6 > p2 > p3 > pA @ 20").unwrap(),
+        ];
+        let mut matcher = Matcher::new(60, 10, 10);
+        let inputs = (0..4)
+            .map(|_| Key::DOWN)
+            .chain((0..5).map(|_| Key::FD))
+            .chain((0..5).map(|_| Key::FORWARD));
+        for input in inputs {
+            matcher.update(input);
+            assert_eq!(matcher.judge_all(&commands).count(), 0);
+        }
+
+        matcher.update(Key::A);
+        let results = matcher.judge_all(&commands).collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![(
+                0,
+                MatchResult {
+                    duration: 15,
+                    max_duration: Some(20)
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn streaming_max_duration() {
+        let command = build_command("p2 > p3 > p6 > pA @ 20").unwrap();
+        let mut matcher = Matcher::new(60, 10, 10);
+        let inputs = (0..4)
+            .map(|_| Key::DOWN)
+            .chain((0..9).map(|_| Key::FD))
+            .chain((0..9).map(|_| Key::FORWARD))
+            .chain((0..1).map(|_| Key::A));
+        for input in inputs {
+            matcher.update(input);
+        }
+        assert_eq!(matcher.judge(&command), None);
+    }
+
+    #[test]
+    fn charge_same_as_history() {
+        // タメ状態を使った判定と入力履歴をさかのぼる判定が一致する
+        let commands = vec![
+            build_command("c4(30)[10] > p6[10] > pC[10]").unwrap(),
+            build_command("p2 > c4(20)[5] > p6").unwrap(),
+            build_command("c2(30) > c8(5)").unwrap(),
+        ];
+        let mut matcher = Matcher::new(120, 10, 10);
+        let inputs = (0..10)
+            .map(|_| Key::empty())
+            .chain((0..4).map(|_| Key::DOWN))
+            .chain((0..20).map(|_| Key::BACKWARD))
+            .chain((0..20).map(|_| Key::BD))
+            .chain((0..3).map(|_| Key::empty()))
+            .chain((0..4).map(|_| Key::FORWARD))
+            .chain((0..2).map(|_| Key::C))
+            .chain((0..30).map(|_| Key::DOWN))
+            .chain((0..10).map(|_| Key::UP))
+            .chain((0..20).map(|_| Key::empty()));
+        let mut matched = 0;
+        for input in inputs {
+            matcher.update(input);
+            for command in &commands {
+                let expected = command.judge(matcher.inputs(), 10, 10);
+                assert_eq!(matcher.judge(command), expected);
+                matched += expected.is_some() as usize;
+            }
+        }
+        assert!(matched > 0);
+    }
+
+    #[test]
+    fn charge_retain() {
+        // 離したあとタメを維持する間は，タメのバッファを過ぎても成立する
+        let command = build_command("c4(30)[5] > p6").unwrap();
+        let inputs = (0..40)
+            .map(|_| Key::BACKWARD)
+            .chain((0..10).map(|_| Key::NEUTRAL))
+            .chain((0..1).map(|_| Key::FORWARD));
+        let mut matcher = Matcher::new(120, 10, 10);
+        let mut retained = Matcher::new(120, 10, 10).with_charge_retain(20);
+        for input in inputs {
+            matcher.update(input);
+            retained.update(input);
+        }
+        assert_eq!(matcher.judge(&command), None);
+        assert!(retained.judge(&command).is_some());
+    }
+
+    #[test]
+    fn charge_retain_beyond_history() {
+        // 離した位置が履歴より前なら，履歴だけで判定したときと同じく不成立
+        let command = build_command("f6 > c4(5)[5]").unwrap();
+        let mut matcher = Matcher::new(10, 10, 10).with_charge_retain(50);
+        let inputs = (0..10)
+            .map(|_| Key::BACKWARD)
+            .chain((0..30).map(|_| Key::NEUTRAL));
+        for input in inputs {
+            matcher.update(input);
+            assert_eq!(
+                matcher.judge(&command).is_some(),
+                command.judge_inputs(matcher.inputs(), 10, 10)
+            );
+        }
+        assert_eq!(matcher.judge(&command), None);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut matcher = Matcher::new(3, 10, 10);
+        for _ in 0..10 {
+            matcher.update(Key::A);
+        }
+        assert_eq!(matcher.inputs().count(), 3);
+    }
+
+    // コマンドの断片を乱数で並べた入力列
+    fn inputs(seed: u64, len: usize) -> Vec<Key> {
+        let fragments: [&[(Key, u64)]; 5] = [
+            &[(Key::DOWN, 2), (Key::FD, 2), (Key::FORWARD, 2), (Key::A, 1)],
+            &[(Key::BACKWARD, 25), (Key::FORWARD, 2), (Key::C, 1)],
+            &[(Key::DOWN, 20), (Key::UP, 2)],
+            &[(Key::FORWARD, 1), (Key::NEUTRAL, 1), (Key::FORWARD, 1)],
+            &[(Key::NEUTRAL, 3)],
+        ];
+        let mut x = seed;
+        let mut next = move || {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            x >> 33
+        };
+        let mut inputs = vec![];
+        while inputs.len() < len {
+            let fragment = fragments[next() as usize % fragments.len()];
+            for &(key, frame) in fragment {
+                // 押している長さをずらす
+                let frame = frame + next() % 3;
+                inputs.extend((0..frame).map(|_| key));
+            }
+        }
+        inputs.truncate(len);
+        inputs
+    }
+
+    #[test]
+    fn rollback_is_deterministic() {
+        let commands = vec![
+            build_command("p2 > p3 > p6 > pA").unwrap(),
+            build_command("c4(20)[10] > p6 > pC").unwrap(),
+            build_command("c2(15) > p8").unwrap(),
+            build_command("dash6").unwrap(),
+        ];
+        let actual = inputs(1, 400);
+        let predicted = inputs(2, 400);
+        let judge = |matcher: &Matcher| {
+            matcher
+                .judge_all(&commands)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        };
+
+        // 巻き戻しなしで実際の入力を処理した結果
+        let mut matcher = Matcher::new(60, 10, 10).with_charge_retain(5);
+        let expected = actual
+            .iter()
+            .map(|&input| {
+                matcher.update(input);
+                judge(&matcher)
+            })
+            .collect::<Vec<_>>();
+        assert!(expected.iter().any(|matched| !matched.is_empty()));
+
+        // 予測した入力で進め，入力が確定したら巻き戻して再計算する
+        let mut matcher = Matcher::new(60, 10, 10).with_charge_retain(5);
+        let delay = 7;
+        let mut states = vec![];
+        let mut results = vec![];
+        for frame in 0..actual.len() {
+            states.push(matcher.save_state());
+            matcher.update(predicted[frame]);
+            results.push(judge(&matcher));
+
+            if frame < delay {
+                continue;
+            }
+            let confirmed = frame - delay;
+            matcher.load_state(&states[confirmed]);
+            for resimulated in confirmed..=frame {
+                matcher.save_state_into(&mut states[resimulated]);
+                let input = if resimulated <= confirmed {
+                    actual[resimulated]
+                } else {
+                    predicted[resimulated]
+                };
+                matcher.update(input);
+                results[resimulated] = judge(&matcher);
+            }
+        }
+        let confirmed = actual.len() - delay;
+        assert_eq!(results[..confirmed], expected[..confirmed]);
+    }
+
+    #[test]
+    fn state_ron() {
+        let mut matcher = Matcher::new(8, 10, 10);
+        for input in inputs(3, 16) {
+            matcher.update(input);
+        }
+        let state = matcher.save_state();
+        let text = ron::ser::to_string(&state).unwrap();
+        let loaded: MatcherState = ron::de::from_str(&text).unwrap();
+        assert_eq!(loaded, state);
+
+        // 保持数を超える履歴は古いものから捨てる
+        let mut small = Matcher::new(4, 10, 10);
+        small.load_state(&loaded);
+        assert_eq!(
+            small.inputs().collect::<Vec<_>>(),
+            state.inputs()[4..].to_vec()
+        );
+    }
+}
@@ -0,0 +1,394 @@
+// 記録したフレームごとの入力を再生してコマンドの成立を確認する
+use crate::{
+    command::{Command, MatchResult},
+    error::{Error, ResourceError},
+    matcher::Matcher,
+    resource::move_list::MoveList,
+    types::{build_key, button::Key},
+};
+
+const MAGIC: &[u8; 4] = b"CPIL";
+const VERSION: u8 = 1;
+// frame(u32) + key(u16) + facing(u8)
+const RECORD_SIZE: usize = 7;
+
+// 記録時のキャラクターの向き
+// 向きがある場合の入力は絶対方向 (6 が右)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facing {
+    Right,
+    Left,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogFrame {
+    pub frame: u32,
+    pub key: Key,
+    pub facing: Option<Facing>,
+}
+
+impl LogFrame {
+    // 前後の入力に変換
+    pub fn relative_key(&self) -> Key {
+        match self.facing {
+            Some(Facing::Left) => self.key.mirrored(),
+            _ => self.key,
+        }
+    }
+}
+
+// 入力の記録
+// 入力が変化したフレームだけを持ち，間のフレームは直前の入力が続く
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InputLog {
+    frames: Vec<LogFrame>,
+}
+
+// 再生中にコマンドが成立したフレーム
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Firing<'a> {
+    pub frame: u32,
+    pub name: &'a str,
+    pub result: MatchResult,
+}
+
+impl InputLog {
+    pub fn new() -> Self {
+        InputLog::default()
+    }
+
+    // 1F ずつの入力から作る
+    pub fn from_inputs(inputs: impl IntoIterator<Item = Key>) -> Self {
+        let mut log = InputLog::new();
+        let mut last = None;
+        for (frame, key) in inputs.into_iter().enumerate() {
+            last = Some((frame as u32, key));
+            if log.frames.last().map(|f| f.key) != Some(key) {
+                log.frames.push(LogFrame {
+                    frame: frame as u32,
+                    key,
+                    facing: None,
+                });
+            }
+        }
+        // 記録の長さを保つため最後のフレームも残す
+        if let Some((frame, key)) = last {
+            if log.frames.last().map(|f| f.frame) != Some(frame) {
+                log.frames.push(LogFrame {
+                    frame,
+                    key,
+                    facing: None,
+                });
+            }
+        }
+        log
+    }
+
+    // フレーム番号は増えていく必要がある
+    pub fn push(&mut self, frame: LogFrame) -> Result<(), Error> {
+        if let Some(last) = self.frames.last() {
+            if frame.frame <= last.frame {
+                return Err(ResourceError::InvalidInputLog {
+                    position: self.frames.len(),
+                    reason: format!("frame {} is not after {}", frame.frame, last.frame),
+                }
+                .into());
+            }
+        }
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    pub fn frames(&self) -> &[LogFrame] {
+        &self.frames
+    }
+
+    // 間を埋めた1F ずつの前後基準の入力
+    pub fn inputs(&self) -> impl Iterator<Item = (u32, Key)> + '_ {
+        self.frames.iter().enumerate().flat_map(move |(i, f)| {
+            // 最後のフレームが u32::MAX でもあふれないよう終わりを含める
+            let last = self
+                .frames
+                .get(i + 1)
+                .map(|n| n.frame - 1)
+                .unwrap_or(f.frame);
+            let key = f.relative_key();
+            (f.frame..=last).map(move |frame| (frame, key))
+        })
+    }
+
+    // 1行1フレーム: フレーム番号 入力 [向き]
+    // # 以降はコメント，入力なしは -
+    // 0 5 R
+    // 10 2
+    // 12 3A L
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut log = InputLog::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |reason: String| -> Error {
+                ResourceError::InvalidInputLog {
+                    position: i + 1,
+                    reason,
+                }
+                .into()
+            };
+
+            let mut fields = line.split_whitespace();
+            let frame = fields
+                .next()
+                .unwrap_or("")
+                .parse()
+                .map_err(|err| invalid(format!("{}", err)))?;
+            let key = match fields.next() {
+                Some("-") => Key::empty(),
+                Some(key) => build_key(key).map_err(|err| invalid(err.to_string()))?,
+                None => return Err(invalid("missing key".into())),
+            };
+            let facing = match fields.next() {
+                Some("R") => Some(Facing::Right),
+                Some("L") => Some(Facing::Left),
+                Some(facing) => return Err(invalid(format!("unknown facing: {}", facing))),
+                None => None,
+            };
+            if let Some(rest) = fields.next() {
+                return Err(invalid(format!("unexpected field: {}", rest)));
+            }
+
+            log.push(LogFrame { frame, key, facing })
+                .map_err(|err| invalid(err.to_string()))?;
+        }
+        Ok(log)
+    }
+
+    // MAGIC, VERSION, 記録数(u32) に続けて各記録をリトルエンディアンで並べる
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + self.frames.len() * RECORD_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for f in &self.frames {
+            bytes.extend_from_slice(&f.frame.to_le_bytes());
+            bytes.extend_from_slice(&f.key.bits().to_le_bytes());
+            bytes.push(match f.facing {
+                None => 0,
+                Some(Facing::Right) => 1,
+                Some(Facing::Left) => 2,
+            });
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |position, reason: &str| -> Error {
+            ResourceError::InvalidInputLog {
+                position,
+                reason: reason.into(),
+            }
+            .into()
+        };
+        if bytes.len() < 9 || &bytes[..4] != MAGIC {
+            return Err(invalid(0, "not an input log"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid(4, "unsupported version"));
+        }
+        let count = u32::from_le_bytes([bytes[5], bytes[6], bytes[7], bytes[8]]) as usize;
+        let records = &bytes[9..];
+        if records.len() != count.saturating_mul(RECORD_SIZE) {
+            return Err(invalid(9, "record count mismatch"));
+        }
+
+        let mut log = InputLog::new();
+        for (i, r) in records.chunks(RECORD_SIZE).enumerate() {
+            let position = 9 + i * RECORD_SIZE;
+            let frame = u32::from_le_bytes([r[0], r[1], r[2], r[3]]);
+            let key = Key::from_bits(u16::from_le_bytes([r[4], r[5]]))
+                .ok_or_else(|| invalid(position + 4, "unknown key bits"))?;
+            let facing = match r[6] {
+                0 => None,
+                1 => Some(Facing::Right),
+                2 => Some(Facing::Left),
+                _ => return Err(invalid(position + 6, "unknown facing")),
+            };
+            log.push(LogFrame { frame, key, facing })
+                .map_err(|err| invalid(position, &err.to_string()))?;
+        }
+        Ok(log)
+    }
+
+    // コマンドが成立し始めたフレームを列挙
+    // 成立し続けている間は最初のフレームだけ
+    pub fn replay(&self, command: &Command, matcher: &mut Matcher) -> Vec<u32> {
+        matcher.clear();
+        let mut fired = vec![];
+        let mut last = false;
+        for (frame, key) in self.inputs() {
+            matcher.update(key);
+            let matched = matcher.judge(command).is_some();
+            if matched && !last {
+                fired.push(frame);
+            }
+            last = matched;
+        }
+        fired
+    }
+
+    // 技ごとに成立し始めたフレームを列挙
+    // 同じフレームでは優先度の高い順
+    pub fn replay_move_list<'a>(
+        &self,
+        move_list: &'a MoveList,
+        matcher: &mut Matcher,
+    ) -> Vec<Firing<'a>> {
+        matcher.clear();
+        let mut fired = vec![];
+        let mut last = vec![false; move_list.moves.len()];
+        for (frame, key) in self.inputs() {
+            matcher.update(key);
+            let mut results = move_list.judge_all(matcher).collect::<Vec<_>>();
+            results.sort_by_key(|&(i, _)| std::cmp::Reverse(move_list.moves[i].priority));
+
+            let mut matched = vec![false; last.len()];
+            for (index, result) in results {
+                matched[index] = true;
+                if !last[index] {
+                    fired.push(Firing {
+                        frame,
+                        name: &move_list.moves[index].name,
+                        result,
+                    });
+                }
+            }
+            last = matched;
+        }
+        fired
+    }
+}
+
+impl std::fmt::Display for InputLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for frame in &self.frames {
+            write!(f, "{} ", frame.frame)?;
+            if frame.key.is_empty() {
+                f.write_str("-")?;
+            } else {
+                write!(f, "{}", frame.key)?;
+            }
+            match frame.facing {
+                Some(Facing::Right) => f.write_str(" R")?,
+                Some(Facing::Left) => f.write_str(" L")?,
+                None => {}
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for InputLog {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InputLog::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "
+# 左向きで 236A
+0 5 L
+10 2 L
+12 1 L
+14 4A L
+16 5 L
+30 5
+";
+
+    const MOVE_LIST: &str = r#"(
+    moves: [
+        (name: "fireball", command: "p2[15] > p3[15] > p6A[15]", priority: 1),
+        (name: "dp", command: "p6[20] > p2[15] > p3A[15]", priority: 2),
+    ],
+)"#;
+
+    #[test]
+    fn parse() {
+        let log = InputLog::parse(LOG).unwrap();
+        assert_eq!(log.frames().len(), 6);
+        assert_eq!(log.frames()[3].relative_key(), Key::FORWARD | Key::A);
+        assert_eq!(log.inputs().count(), 31);
+        assert_eq!(log.inputs().nth(11), Some((11, Key::DOWN)));
+        assert_eq!(InputLog::parse(&log.to_string()).unwrap(), log);
+
+        InputLog::parse("0 5\n0 6").unwrap_err();
+        InputLog::parse("0 5 X").unwrap_err();
+        InputLog::parse("0").unwrap_err();
+        InputLog::parse("a 5").unwrap_err();
+    }
+
+    #[test]
+    fn binary() {
+        let log = InputLog::parse(LOG).unwrap();
+        let bytes = log.to_bytes();
+        assert_eq!(InputLog::from_bytes(&bytes).unwrap(), log);
+
+        InputLog::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+        InputLog::from_bytes(b"XXXX").unwrap_err();
+    }
+
+    #[test]
+    fn last_frame_max() {
+        let log = InputLog::parse(&format!("{} 5\n{} 2", u32::MAX - 1, u32::MAX)).unwrap();
+        assert_eq!(
+            log.inputs().collect::<Vec<_>>(),
+            vec![(u32::MAX - 1, Key::NEUTRAL), (u32::MAX, Key::DOWN)]
+        );
+        let log = InputLog::from_bytes(&log.to_bytes()).unwrap();
+        assert_eq!(log.inputs().last(), Some((u32::MAX, Key::DOWN)));
+    }
+
+    #[test]
+    fn from_inputs() {
+        let inputs = vec![Key::NEUTRAL, Key::NEUTRAL, Key::DOWN, Key::DOWN];
+        let log = InputLog::from_inputs(inputs.clone());
+        assert_eq!(log.to_string(), "0 5\n2 2\n3 2\n");
+        assert_eq!(log.inputs().map(|(_, key)| key).collect::<Vec<_>>(), inputs);
+    }
+
+    #[test]
+    fn replay() {
+        let log = InputLog::parse(LOG).unwrap();
+        let command = Command::build("p2[15] > p3[15] > p6A[15]").unwrap();
+        let mut matcher = Matcher::new(60, 10, 10);
+        assert_eq!(log.replay(&command, &mut matcher), vec![14]);
+    }
+
+    #[test]
+    fn replay_move_list() {
+        let list = MoveList::from_ron(MOVE_LIST).unwrap();
+        let mut matcher = Matcher::new(60, 10, 10);
+
+        // 6 から入力しても最後が 6A なら波動
+        let log = InputLog::parse("0 6\n2 2\n4 3\n6 6A\n8 5").unwrap();
+        let fired = log.replay_move_list(&list, &mut matcher);
+        assert_eq!(
+            fired.iter().map(|f| (f.frame, f.name)).collect::<Vec<_>>(),
+            vec![(6, "fireball")]
+        );
+
+        // 3A で止めれば昇竜
+        let log = InputLog::parse("0 6\n2 2\n4 3A\n6 5").unwrap();
+        let fired = log.replay_move_list(&list, &mut matcher);
+        assert_eq!(
+            fired.iter().map(|f| (f.frame, f.name)).collect::<Vec<_>>(),
+            vec![(4, "dp")]
+        );
+    }
+}
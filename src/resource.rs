@@ -1,2 +1,4 @@
 pub(crate) mod de;
+pub mod move_list;
 pub(crate) mod ser;
+pub mod structured;
@@ -1,28 +1,292 @@
-use crate::{command::Command, types::build_command};
-use serde::de::{self, Deserialize, Deserializer, Visitor};
+use crate::{
+    command::Command,
+    error,
+    resource::structured::{StructuredCommand, StructuredCommandKey},
+    types::{build_command, build_command_from_keys, build_command_key, build_key},
+    CommandKey, Key,
+};
+use serde::de::{self, value, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
 use std::fmt;
 
-struct CommandVisitor;
+// 入力の形式が正しければ，コマンドとしての検証結果を返す
+pub(crate) struct CommandVisitor;
 
 impl<'de> Visitor<'de> for CommandVisitor {
-    type Value = Command;
+    type Value = Result<Command, error::Error>;
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("require command text format")
+        formatter.write_str("require command text format or sequence of command keys")
     }
 
     fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        build_command(s).map_err(de::Error::custom)
+        Ok(build_command(s))
+    }
+
+    // キー入力の一覧: [Push(key: ["DOWN"]), Push(key: ["FORWARD"])]
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let keys =
+            Vec::<StructuredCommandKey>::deserialize(value::SeqAccessDeserializer::new(seq))?;
+        Ok(build_command_from_keys(
+            keys.into_iter().map(|k| k.0).collect(),
+            None,
+        ))
+    }
+
+    // 制限時間付き: (keys: [...], max_duration: Some(30))
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let command = StructuredCommand::deserialize(value::MapAccessDeserializer::new(map))?;
+        Ok(build_command_from_keys(
+            command.keys.into_iter().map(|k| k.0).collect(),
+            command.max_duration,
+        ))
     }
 }
 
+// 記法の文字列，またはキー入力の一覧から作る
+// 自己記述的でない形式では構造化した形式を判別できないので，文字列として要求する
 impl<'de> Deserialize<'de> for Command {
     fn deserialize<D>(deserializer: D) -> Result<Command, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(CommandVisitor)
+        let command = if deserializer.is_human_readable() {
+            deserializer.deserialize_any(CommandVisitor)?
+        } else {
+            deserializer.deserialize_str(CommandVisitor)?
+        };
+        command.map_err(de::Error::custom)
+    }
+}
+
+struct CommandKeyVisitor;
+
+impl<'de> Visitor<'de> for CommandKeyVisitor {
+    type Value = CommandKey;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("require command key text format")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        build_command_key(s).map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for CommandKey {
+    fn deserialize<D>(deserializer: D) -> Result<CommandKey, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(CommandKeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Key;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("require key text format")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        build_key(s).map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D>(deserializer: D) -> Result<Key, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource::structured;
+    use serde::forward_to_deserialize_any;
+
+    // 構造化した形式を受け付ける指定
+    #[derive(Debug, serde::Deserialize)]
+    #[serde(transparent)]
+    struct Structured(#[serde(with = "structured::command")] Command);
+
+    // bincode のように型から形式が決まり，deserialize_any を使えない形式
+    struct StrOnly<'a>(&'a str);
+
+    impl<'de, 'a> Deserializer<'de> for StrOnly<'a> {
+        type Error = value::Error;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn deserialize_any<V>(self, _: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            Err(de::Error::custom("deserialize_any is not supported"))
+        }
+
+        fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_str(self.0)
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    #[test]
+    fn deserialize_command_without_any() {
+        let command = Command::deserialize(StrOnly("p2 > p3 > p6 > pA")).unwrap();
+        assert_eq!(command, build_command("p2 > p3 > p6 > pA").unwrap());
+    }
+
+    #[test]
+    fn deserialize_structured_command() {
+        let expected = build_command("h4(60)[10] > p6[10] > pC").unwrap();
+        let Structured(command) = ron::de::from_str(
+            r#"[
+                Hold(key: ["BACKWARD"], hold_frame: Some(60), buffer_frame: Some(10)),
+                Push(key: ["FORWARD"], buffer_frame: Some(10)),
+                Push(key: ["C"]),
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(command, expected);
+
+        let Structured(command) = serde_json::from_str(
+            r#"[
+                {"Hold": {"key": ["BACKWARD"], "hold_frame": 60, "buffer_frame": 10}},
+                {"Push": {"key": ["FORWARD"], "buffer_frame": 10}},
+                {"Push": {"key": ["C"]}}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(command, expected);
+
+        // 文字列も引き続き受け付ける
+        let Structured(command) = serde_json::from_str(r#""h4(60)[10] > p6[10] > pC""#).unwrap();
+        assert_eq!(command, expected);
+        // 指定しなくても受け付ける
+        let command = serde_json::from_str::<Command>(r#"[{"Push": {"key": ["C"]}}]"#).unwrap();
+        assert_eq!(command, build_command("pC").unwrap());
+    }
+
+    #[test]
+    fn deserialize_command_field() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Move {
+            command: Command,
+        }
+
+        let expected = build_command("p2 > pA @ 30").unwrap();
+        let sources = [
+            r#"(command: "p2 > pA @ 30")"#,
+            r#"(command: (keys: [Push(key: ["DOWN"]), Push(key: ["A"])], max_duration: Some(30)))"#,
+        ];
+        for source in &sources {
+            let m: Move = ron::de::from_str(source).unwrap();
+            assert_eq!(m.command, expected, "{}", source);
+        }
+        let sources = [
+            r#"{"command": "p2 > pA @ 30"}"#,
+            r#"{"command": {"keys": [{"Push": {"key": ["DOWN"]}}, {"Push": {"key": ["A"]}}], "max_duration": 30}}"#,
+        ];
+        for source in &sources {
+            let m: Move = serde_json::from_str(source).unwrap();
+            assert_eq!(m.command, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn deserialize_structured_command_map() {
+        let expected = build_command("p2 > p3 > p6 > pA @ 30").unwrap();
+        let Structured(command) = ron::de::from_str(
+            r#"(
+                keys: [
+                    Push(key: ["DOWN"]),
+                    Push(key: ["FD"]),
+                    Push(key: ["FORWARD"]),
+                    Push(key: ["A"]),
+                ],
+                max_duration: Some(30),
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(command, expected);
+
+        let Structured(command) = serde_json::from_str(
+            r#"{
+                "keys": [
+                    {"Push": {"key": ["DOWN"]}},
+                    {"Push": {"key": ["FD"]}},
+                    {"Push": {"key": ["FORWARD"]}},
+                    {"Push": {"key": ["A"]}}
+                ],
+                "max_duration": 30
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(command, expected);
+    }
+
+    #[test]
+    fn deserialize_structured_command_fail() {
+        // 記法と同じく空のキーは不可
+        serde_json::from_str::<Structured>(r#"[{"Push": {"key": []}}]"#).unwrap_err();
+        ron::de::from_str::<Structured>(r#"[Push(key: ["E"])]"#).unwrap_err();
+        serde_json::from_str::<Structured>(r#"{"max_duration": 30}"#).unwrap_err();
+        // キー入力が多すぎても panic しない
+        let keys = vec![r#"Push(key: ["A"])"#; Command::MAX_KEYS + 1].join(",");
+        ron::de::from_str::<Command>(&format!("[{}]", keys)).unwrap_err();
+    }
+
+    #[test]
+    fn deserialize_command_key() {
+        let keys: Vec<CommandKey> =
+            ron::de::from_str(r#"["c4(60,3)[10]", "p6", "pA*3{20}"]"#).unwrap();
+        let expected = build_command("c4(60,3)[10] > p6 > pA*3{20}").unwrap();
+        assert_eq!(keys, expected.keys().copied().collect::<Vec<_>>());
+
+        let keys: Vec<CommandKey> =
+            serde_json::from_str(r#"["c4(60,3)[10]", "p6", "pA*3{20}"]"#).unwrap();
+        assert_eq!(keys, expected.keys().copied().collect::<Vec<_>>());
+
+        // 複数のキーは不可
+        ron::de::from_str::<CommandKey>(r#""p2 > p6""#).unwrap_err();
+        serde_json::from_str::<CommandKey>(r#""dash6""#).unwrap_err();
+    }
+
+    #[test]
+    fn deserialize_key() {
+        let keys: Vec<Key> = ron::de::from_str(r#"["6A", ""]"#).unwrap();
+        assert_eq!(keys, vec![Key::FORWARD | Key::A, Key::empty()]);
+        let keys: Vec<Key> = serde_json::from_str(r#"["6A", ""]"#).unwrap();
+        assert_eq!(keys, vec![Key::FORWARD | Key::A, Key::empty()]);
+
+        serde_json::from_str::<Key>(r#""6E""#).unwrap_err();
     }
 }
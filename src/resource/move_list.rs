@@ -0,0 +1,472 @@
+use crate::{
+    command::{Command, MatchResult},
+    config::{JudgeConfig, JudgeOverride},
+    error::{Error, ResourceError},
+    matcher::Matcher,
+    resource::de::CommandVisitor,
+    types::{build_command_with_macros, check_macro, Macros},
+};
+use serde::{
+    de::{self, Deserializer, MapAccess, SeqAccess, Visitor},
+    Deserialize, Serialize,
+};
+use std::{collections::BTreeSet, fmt};
+
+// 技の分類
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub enum Category {
+    #[default]
+    Normal,
+    Special,
+    Super,
+    System,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Move {
+    pub name: String,
+    pub command: Command,
+    // 同時に成立したときは大きいものを優先
+    pub priority: i32,
+    pub category: Category,
+    // 使用に必要なリソース (ゲージ，空中など)
+    pub resources: Vec<String>,
+    // キャンセル可能な技の分類
+    pub cancel_tags: Vec<String>,
+    // 技一覧の判定設定の上書き
+    #[serde(skip_serializing_if = "JudgeOverride::is_empty")]
+    pub config: JudgeOverride,
+}
+
+// キャラクターごとの技一覧
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct MoveList {
+    // 記法の中で $名前 として参照できる断片
+    pub macros: Macros,
+    #[serde(skip_serializing_if = "is_default")]
+    pub config: JudgeConfig,
+    pub moves: Vec<Move>,
+}
+
+impl MoveList {
+    pub fn from_ron(data: &str) -> Result<MoveList, Error> {
+        let raw: RawMoveList = ron::de::from_str(data)?;
+        raw.validate()
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json(data: &str) -> Result<MoveList, Error> {
+        let raw: RawMoveList = serde_json::from_str(data)?;
+        raw.validate()
+    }
+
+    pub fn to_ron(&self) -> Result<String, Error> {
+        let config = ron::ser::PrettyConfig::default();
+        Ok(ron::ser::to_string_pretty(self, config)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Move> {
+        self.moves.iter().find(|m| m.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Move> {
+        self.moves.iter()
+    }
+
+    // 技一覧の判定設定を使う Matcher
+    pub fn matcher(&self, capacity: usize) -> Matcher {
+        Matcher::from_config(capacity, self.config)
+    }
+
+    // 成立した技の番号と結果を列挙
+    // 技ごとの上書きは Matcher の設定に対して行う
+    pub fn judge_all<'a>(
+        &'a self,
+        matcher: &'a Matcher,
+    ) -> impl Iterator<Item = (usize, MatchResult)> + 'a {
+        self.moves.iter().enumerate().filter_map(move |(i, m)| {
+            let config = m.config.apply(matcher.config());
+            matcher
+                .judge_with_config(&m.command, &config)
+                .map(|result| (i, result))
+        })
+    }
+
+    // 成立した技を優先度の高い順に列挙
+    pub fn judge<'a>(&'a self, matcher: &'a Matcher) -> Vec<(&'a Move, MatchResult)> {
+        let mut matched = self
+            .judge_all(matcher)
+            .map(|(i, result)| (&self.moves[i], result))
+            .collect::<Vec<_>>();
+        matched.sort_by_key(|(m, _)| std::cmp::Reverse(m.priority));
+        matched
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveList {
+    fn deserialize<D>(deserializer: D) -> Result<MoveList, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        RawMoveList::deserialize(deserializer)?
+            .validate()
+            .map_err(de::Error::custom)
+    }
+}
+
+// 検証前の技一覧
+#[derive(Deserialize)]
+struct RawMoveList {
+    #[serde(default)]
+    macros: Macros,
+    #[serde(default)]
+    config: JudgeConfig,
+    moves: Vec<RawMove>,
+}
+
+#[derive(Deserialize)]
+struct RawMove {
+    name: String,
+    command: RawCommand,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    category: Category,
+    #[serde(default)]
+    resources: Vec<String>,
+    #[serde(default)]
+    cancel_tags: Vec<String>,
+    #[serde(default)]
+    config: JudgeOverride,
+}
+
+// コマンドの検証エラーは技の名前と合わせて返すので保持しておく
+enum RawCommand {
+    // 記法は断片の定義を読んだあとに変換する
+    Notation(String),
+    Structured(Box<Result<Command, Error>>),
+}
+
+struct RawCommandVisitor;
+
+impl<'de> Visitor<'de> for RawCommandVisitor {
+    type Value = RawCommand;
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        CommandVisitor.expecting(formatter)
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawCommand::Notation(s.into()))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        CommandVisitor
+            .visit_seq(seq)
+            .map(|command| RawCommand::Structured(Box::new(command)))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        CommandVisitor
+            .visit_map(map)
+            .map(|command| RawCommand::Structured(Box::new(command)))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawCommand {
+    fn deserialize<D>(deserializer: D) -> Result<RawCommand, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(RawCommandVisitor)
+    }
+}
+
+impl RawMoveList {
+    fn validate(self) -> Result<MoveList, Error> {
+        // 使われていない断片も検証する
+        for name in self.macros.keys() {
+            check_macro(name, &self.macros)?;
+        }
+
+        let macros = self.macros;
+        let mut names = BTreeSet::new();
+        let moves = self
+            .moves
+            .into_iter()
+            .map(|m| {
+                if !names.insert(m.name.clone()) {
+                    return Err(ResourceError::DuplicateMove { name: m.name }.into());
+                }
+                let command = match m.command {
+                    RawCommand::Notation(notation) => build_command_with_macros(&notation, &macros),
+                    RawCommand::Structured(command) => *command,
+                };
+                let command = match command {
+                    Ok(command) => command,
+                    Err(err) => {
+                        return Err(ResourceError::InvalidMove {
+                            name: m.name,
+                            source: Box::new(err),
+                        }
+                        .into())
+                    }
+                };
+                Ok(Move {
+                    name: m.name,
+                    command,
+                    priority: m.priority,
+                    category: m.category,
+                    resources: m.resources,
+                    cancel_tags: m.cancel_tags,
+                    config: m.config,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(MoveList {
+            macros,
+            config: self.config,
+            moves,
+        })
+    }
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{types::build_command, Key};
+
+    const MOVE_LIST: &str = r#"(
+        moves: [
+            (
+                name: "hadouken",
+                command: "p2 > p3 > p6 > pA",
+                priority: 10,
+                category: Special,
+                cancel_tags: ["normal"],
+            ),
+            (
+                name: "shinku_hadouken",
+                command: "p2 > p3 > p6 > p2 > p3 > p6 > pA @ 40",
+                priority: 20,
+                category: Super,
+                resources: ["meter"],
+            ),
+            (
+                name: "forward_dash",
+                command: [
+                    Push(key: ["FORWARD"], buffer_frame: Some(12)),
+                    Off(key: ["NEUTRAL"]),
+                    Push(key: ["FORWARD"]),
+                ],
+                category: System,
+            ),
+            (
+                name: "jab",
+                command: "pA",
+            ),
+        ],
+    )"#;
+
+    #[test]
+    fn load_ron() {
+        let list = MoveList::from_ron(MOVE_LIST).unwrap();
+        assert_eq!(list.moves.len(), 4);
+
+        let hadouken = list.get("hadouken").unwrap();
+        assert_eq!(
+            hadouken.command,
+            build_command("p2 > p3 > p6 > pA").unwrap()
+        );
+        assert_eq!(hadouken.category, Category::Special);
+        assert_eq!(hadouken.cancel_tags, vec!["normal".to_string()]);
+
+        let dash = list.get("forward_dash").unwrap();
+        assert_eq!(dash.command, crate::system::forward_dash(12));
+        assert_eq!(dash.priority, 0);
+
+        let jab = list.get("jab").unwrap();
+        assert_eq!(jab.category, Category::Normal);
+        assert!(jab.resources.is_empty());
+
+        // 書き出したものを読み直しても同じ
+        let ron = list.to_ron().unwrap();
+        assert_eq!(MoveList::from_ron(&ron).unwrap(), list);
+    }
+
+    #[test]
+    fn load_macros() {
+        let list = MoveList::from_ron(
+            r#"(
+                macros: {
+                    "qcf": "p2[8] > p3[8] > p6[8]",
+                    "hcf": "p4 > p1 > $qcf",
+                },
+                moves: [
+                    (name: "hadouken", command: "$qcf > pA"),
+                    (name: "command_grab", command: "$hcf > pC"),
+                ],
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            list.get("hadouken").unwrap().command,
+            build_command("p2[8] > p3[8] > p6[8] > pA").unwrap()
+        );
+        assert_eq!(
+            list.get("command_grab").unwrap().command,
+            build_command("p4 > p1 > p2[8] > p3[8] > p6[8] > pC").unwrap()
+        );
+        assert_eq!(MoveList::from_ron(&list.to_ron().unwrap()).unwrap(), list);
+
+        // 定義されていない名前は技の名前と合わせて返す
+        let err = MoveList::from_ron(
+            r#"(macros: {"qcf": "p2 > p3 > p6"}, moves: [(name: "hadouken", command: "$qcb > pA")])"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("\"hadouken\""), "{}", err);
+        assert!(err.to_string().contains("$qcb"), "{}", err);
+
+        // 使われていない断片の循環もエラー
+        let err = MoveList::from_ron(
+            r#"(macros: {"a": "p2 > $b", "b": "$a"}, moves: [(name: "jab", command: "pA")])"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("recursive"), "{}", err);
+    }
+
+    #[test]
+    fn load_fail() {
+        // エラーには技の名前が含まれる
+        let err = MoveList::from_ron(
+            r#"(moves: [(name: "jab", command: "pA"), (name: "broken", command: "p2 > pX")])"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("\"broken\""), "{}", err);
+
+        let err = MoveList::from_ron(r#"(moves: [(name: "empty", command: [Push(key: [])])])"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("\"empty\""), "{}", err);
+
+        let err = MoveList::from_ron(
+            r#"(moves: [(name: "jab", command: "pA"), (name: "jab", command: "pB")])"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("jab"), "{}", err);
+
+        // 技の名前と元のエラーを取り出せる
+        let err =
+            MoveList::from_ron(r#"(moves: [(name: "broken", command: "p2 > $qcf")])"#).unwrap_err();
+        match err {
+            Error::Resource(ResourceError::InvalidMove { name, source }) => {
+                assert_eq!(name, "broken");
+                assert!(matches!(*source, Error::Parse(_)), "{:?}", source);
+            }
+            other => panic!("{:?}", other),
+        }
+        match MoveList::from_ron("(moves: [") {
+            Err(Error::Resource(ResourceError::Ron(_))) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn load_config() {
+        let list = MoveList::from_ron(
+            r#"(
+                config: (buffer: 8, negative_edge: true, socd: Neutral),
+                moves: [
+                    (name: "jab", command: "pA"),
+                    (name: "slow_fireball", command: "p2 > p6 > pA", config: (buffer: Some(20))),
+                ],
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            list.config,
+            JudgeConfig {
+                buffer: 8,
+                negative_edge: true,
+                socd: crate::Socd::Neutral,
+                ..JudgeConfig::default()
+            }
+        );
+        assert!(list.get("jab").unwrap().config.is_empty());
+        assert_eq!(list.get("slow_fireball").unwrap().config.buffer, Some(20));
+        assert_eq!(MoveList::from_ron(&list.to_ron().unwrap()).unwrap(), list);
+
+        // 技一覧のバッファでは届かないが，上書きしたバッファなら届く
+        let mut matcher = list.matcher(60);
+        assert_eq!(matcher.config(), &list.config);
+        let inputs = (0..1)
+            .map(|_| Key::DOWN)
+            .chain((0..12).map(|_| Key::empty()))
+            .chain((0..1).map(|_| Key::FORWARD))
+            .chain((0..1).map(|_| Key::A));
+        for input in inputs {
+            matcher.update(input);
+        }
+        let names = list
+            .judge(&matcher)
+            .into_iter()
+            .map(|(m, _)| m.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["jab", "slow_fireball"]);
+        let fireball = &list.get("slow_fireball").unwrap().command;
+        assert_eq!(matcher.judge(fireball), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_json() {
+        let list = MoveList::from_json(
+            r#"{
+                "moves": [
+                    {"name": "hadouken", "command": "p2 > p3 > p6 > pA", "category": "Special"},
+                    {"name": "jab", "command": [{"Push": {"key": ["A"]}}]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(list, MoveList::from_ron(&list.to_ron().unwrap()).unwrap());
+        assert_eq!(
+            list.get("jab").unwrap().command,
+            build_command("pA").unwrap()
+        );
+    }
+
+    #[test]
+    fn judge_priority() {
+        let list = MoveList::from_ron(MOVE_LIST).unwrap();
+        let mut matcher = Matcher::new(60, 10, 10);
+        let inputs = (0..3)
+            .map(|_| Key::DOWN)
+            .chain((0..3).map(|_| Key::FD))
+            .chain((0..3).map(|_| Key::FORWARD))
+            .chain((0..1).map(|_| Key::A));
+        for input in inputs {
+            matcher.update(input);
+        }
+        let names = list
+            .judge(&matcher)
+            .into_iter()
+            .map(|(m, _)| m.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["hadouken", "jab"]);
+    }
+}
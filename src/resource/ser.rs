@@ -1,4 +1,4 @@
-use crate::{command::Command, CommandKey};
+use crate::{command::Command, CommandKey, Key};
 use serde::ser::{Serialize, Serializer};
 
 impl Serialize for Command {
@@ -6,59 +6,164 @@ impl Serialize for Command {
     where
         S: Serializer,
     {
-        let ser: String = self
-            .keys()
-            .map(|k| match k {
-                CommandKey::Push { key, buffer_frame } => {
-                    let mut ser = String::new();
-                    ser.push_str("p");
-                    ser.push_str(&format!("{}", key));
-                    if let Some(buffer_frame) = buffer_frame {
-                        ser.push_str(&format!("[{}]", buffer_frame));
-                    }
-                    ser
-                }
-                CommandKey::Release { key, buffer_frame } => {
-                    let mut ser = String::new();
-                    ser.push_str("r");
-                    ser.push_str(&format!("{}", key));
-                    if let Some(buffer_frame) = buffer_frame {
-                        ser.push_str(&format!("[{}]", buffer_frame));
-                    }
-                    ser
-                }
-                CommandKey::Hold {
-                    key,
-                    hold_frame,
-                    buffer_frame,
-                } => {
-                    let mut ser = String::new();
-                    ser.push_str("h");
-                    ser.push_str(&format!("{}", key));
-                    if let Some(hold_frame) = hold_frame {
-                        ser.push_str(&format!("({})", hold_frame));
-                    }
-                    if let Some(buffer_frame) = buffer_frame {
-                        ser.push_str(&format!("[{}]", buffer_frame));
-                    }
-                    ser
-                }
-                CommandKey::On { key } => {
-                    let mut ser = String::new();
-                    ser.push_str("n");
-                    ser.push_str(&format!("{}", key));
-                    ser
-                }
-                CommandKey::Off { key } => {
-                    let mut ser = String::new();
-                    ser.push_str("f");
-                    ser.push_str(&format!("{}", key));
-                    ser
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(">");
+        let mut ser: String = self.keys().map(notation).collect::<Vec<_>>().join(">");
+        if let Some(max_duration) = self.max_duration() {
+            ser.push_str(&format!("@{}", max_duration));
+        }
 
         serializer.serialize_str(&ser)
     }
 }
+
+impl Serialize for CommandKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&notation(self))
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{}", self))
+    }
+}
+
+pub(crate) fn notation(key: &CommandKey) -> String {
+    match key {
+        CommandKey::Push { key, buffer_frame } => {
+            let mut ser = String::new();
+            ser.push('p');
+            ser.push_str(&format!("{}", key));
+            if let Some(buffer_frame) = buffer_frame {
+                ser.push_str(&format!("[{}]", buffer_frame));
+            }
+            ser
+        }
+        CommandKey::Release { key, buffer_frame } => {
+            let mut ser = String::new();
+            ser.push('r');
+            ser.push_str(&format!("{}", key));
+            if let Some(buffer_frame) = buffer_frame {
+                ser.push_str(&format!("[{}]", buffer_frame));
+            }
+            ser
+        }
+        CommandKey::Hold {
+            key,
+            hold_frame,
+            buffer_frame,
+            grace_frame,
+            direction_class,
+        } => {
+            let mut ser = String::new();
+            ser.push(if *direction_class { 'c' } else { 'h' });
+            ser.push_str(&format!("{}", key));
+            match (hold_frame, grace_frame) {
+                (Some(hold_frame), Some(grace_frame)) => {
+                    ser.push_str(&format!("({},{})", hold_frame, grace_frame))
+                }
+                (Some(hold_frame), None) => ser.push_str(&format!("({})", hold_frame)),
+                (None, Some(grace_frame)) => ser.push_str(&format!("(,{})", grace_frame)),
+                (None, None) => {}
+            }
+            if let Some(buffer_frame) = buffer_frame {
+                ser.push_str(&format!("[{}]", buffer_frame));
+            }
+            ser
+        }
+        CommandKey::On { key } => {
+            let mut ser = String::new();
+            ser.push('n');
+            ser.push_str(&format!("{}", key));
+            ser
+        }
+        CommandKey::Off { key } => {
+            let mut ser = String::new();
+            ser.push('f');
+            ser.push_str(&format!("{}", key));
+            ser
+        }
+        CommandKey::Mash {
+            key,
+            count,
+            window_frame,
+        } => {
+            let mut ser = String::new();
+            ser.push('p');
+            ser.push_str(&format!("{}*{}", key, count));
+            if let Some(window_frame) = window_frame {
+                ser.push_str(&format!("{{{}}}", window_frame));
+            }
+            ser
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{types::build_command, Key};
+
+    #[test]
+    fn serialize_mash() {
+        let command = build_command("pB * 4 { 30 } > p B*2").unwrap();
+        let ser = crate::serialize(&command).unwrap();
+        assert_eq!(ser, r#""pB*4{30}>pB*2""#);
+        assert_eq!(crate::deserialize("pB*4{30}>pB*2").unwrap(), command);
+    }
+
+    #[test]
+    fn serialize_hold() {
+        for notation in &["h4(60)[10]", "c4(60,3)[10]", "c2(,3)", "h2"] {
+            let command = build_command(notation).unwrap();
+            let ser = crate::serialize(&command).unwrap();
+            assert_eq!(ser, format!("{:?}", notation));
+        }
+    }
+
+    #[test]
+    fn serialize_command_key() {
+        let command = build_command("c4(60,3)[10] > p6 > pA*3{20}").unwrap();
+        let keys = command.keys().collect::<Vec<_>>();
+        assert_eq!(
+            ron::ser::to_string(&keys).unwrap(),
+            r#"["c4(60,3)[10]","p6","pA*3{20}",]"#
+        );
+        assert_eq!(
+            serde_json::to_string(&keys).unwrap(),
+            r#"["c4(60,3)[10]","p6","pA*3{20}"]"#
+        );
+    }
+
+    #[test]
+    fn serialize_key() {
+        let keys = vec![Key::FORWARD | Key::A, Key::empty()];
+        assert_eq!(ron::ser::to_string(&keys).unwrap(), r#"["6A","",]"#);
+        assert_eq!(serde_json::to_string(&keys).unwrap(), r#"["6A",""]"#);
+    }
+
+    #[test]
+    fn serialize_tap() {
+        // ニュートラルへの戻しは f5 になる
+        let command = build_command("tap6[10] > dash4A").unwrap();
+        let ser = crate::serialize(&command).unwrap();
+        assert_eq!(ser, r#""p6[10]>f5>p4A>f5A>p4A""#);
+        assert_eq!(
+            crate::deserialize("p6[10]>f5>p4A>f5A>p4A").unwrap(),
+            command
+        );
+        assert_eq!(command.to_string(), "6>5>4A>5A>4A");
+    }
+
+    #[test]
+    fn serialize_max_duration() {
+        let command = build_command("p2 > p3 > p6 > pA @ 30").unwrap();
+        let ser = crate::serialize(&command).unwrap();
+        assert_eq!(ser, r#""p2>p3>p6>pA@30""#);
+        assert_eq!(crate::deserialize("p2>p3>p6>pA@30").unwrap(), command);
+    }
+}
@@ -0,0 +1,294 @@
+// 構造化した形式でのシリアライズ
+// #[serde(with = "command_parser::resource::structured::command_key")] のように指定して使う
+use crate::{CommandKey, Key};
+use serde::{Deserialize, Serialize};
+
+// 構造化した形式のキー入力
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub(crate) struct StructuredCommandKey(#[serde(with = "command_key")] pub(crate) CommandKey);
+
+// 制限時間付きのコマンド
+#[derive(Deserialize)]
+pub(crate) struct StructuredCommand {
+    pub(crate) keys: Vec<StructuredCommandKey>,
+    #[serde(default)]
+    pub(crate) max_duration: Option<u32>,
+}
+
+// キー名の一覧: ["FORWARD", "A"]
+pub mod key {
+    use crate::Key;
+    use serde::{
+        de::{self, Deserializer, SeqAccess, Visitor},
+        ser::{SerializeSeq, Serializer},
+    };
+    use std::fmt;
+
+    const NAMES: [(&str, Key); 13] = [
+        ("A", Key::A),
+        ("B", Key::B),
+        ("C", Key::C),
+        ("D", Key::D),
+        ("FORWARD", Key::FORWARD),
+        ("BACKWARD", Key::BACKWARD),
+        ("UP", Key::UP),
+        ("DOWN", Key::DOWN),
+        ("FD", Key::FD),
+        ("FU", Key::FU),
+        ("BD", Key::BD),
+        ("BU", Key::BU),
+        ("NEUTRAL", Key::NEUTRAL),
+    ];
+
+    pub fn serialize<S>(key: &Key, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let names = NAMES.iter().filter(|(_, k)| key.contains(*k));
+        let mut seq = serializer.serialize_seq(Some(names.clone().count()))?;
+        for (name, _) in names {
+            seq.serialize_element(name)?;
+        }
+        seq.end()
+    }
+
+    struct KeyNamesVisitor;
+
+    impl<'de> Visitor<'de> for KeyNamesVisitor {
+        type Value = Key;
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("require sequence of key names")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut key = Key::empty();
+            while let Some(name) = seq.next_element::<String>()? {
+                key |= NAMES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, k)| *k)
+                    .ok_or_else(|| de::Error::custom(format!("unknown key name: {}", name)))?;
+            }
+            Ok(key)
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Key, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(KeyNamesVisitor)
+    }
+}
+
+// バリアントとフィールドを持つ形式: Push(key: ["FORWARD"], buffer_frame: Some(10))
+pub mod command_key {
+    use super::CommandKeyDef;
+    use crate::CommandKey;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(key: &CommandKey, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        CommandKeyDef::serialize(key, serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<CommandKey, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        CommandKeyDef::deserialize(deserializer)
+    }
+}
+
+// キー入力の一覧と制限時間: (keys: [Push(key: ["DOWN"])], max_duration: None)
+// デシリアライズは記法の文字列も受け付ける
+pub mod command {
+    use super::command_key;
+    use crate::{command::Command, resource::de::CommandVisitor, CommandKey};
+    use serde::{
+        de,
+        ser::{SerializeSeq, SerializeStruct},
+        Deserializer, Serialize, Serializer,
+    };
+
+    struct Keys<'a>(&'a Command);
+
+    impl<'a> Serialize for Keys<'a> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut seq = serializer.serialize_seq(Some(self.0.keys().count()))?;
+            for key in self.0.keys() {
+                seq.serialize_element(&Step(key))?;
+            }
+            seq.end()
+        }
+    }
+
+    struct Step<'a>(&'a CommandKey);
+
+    impl<'a> Serialize for Step<'a> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            command_key::serialize(self.0, serializer)
+        }
+    }
+
+    pub fn serialize<S>(command: &Command, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut st = serializer.serialize_struct("Command", 2)?;
+        st.serialize_field("keys", &Keys(command))?;
+        st.serialize_field("max_duration", &command.max_duration())?;
+        st.end()
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Command, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_any(CommandVisitor)?
+            .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "CommandKey")]
+enum CommandKeyDef {
+    Push {
+        #[serde(with = "key")]
+        key: Key,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        buffer_frame: Option<u32>,
+    },
+    Release {
+        #[serde(with = "key")]
+        key: Key,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        buffer_frame: Option<u32>,
+    },
+    Hold {
+        #[serde(with = "key")]
+        key: Key,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        buffer_frame: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hold_frame: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        grace_frame: Option<u32>,
+        #[serde(default)]
+        direction_class: bool,
+    },
+    On {
+        #[serde(with = "key")]
+        key: Key,
+    },
+    Off {
+        #[serde(with = "key")]
+        key: Key,
+    },
+    Mash {
+        #[serde(with = "key")]
+        key: Key,
+        count: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        window_frame: Option<u32>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::build_command;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Step {
+        #[serde(with = "key")]
+        key: Key,
+        #[serde(with = "command_key")]
+        command_key: CommandKey,
+    }
+
+    #[test]
+    fn structured_ron() {
+        let step = Step {
+            key: Key::FORWARD | Key::A,
+            command_key: *build_command("c4(60,3)[10]")
+                .unwrap()
+                .keys()
+                .next()
+                .unwrap(),
+        };
+        let ser = ron::ser::to_string(&step).unwrap();
+        assert_eq!(
+            ser,
+            r#"(key:["A","FORWARD",],command_key:Hold(key:["BACKWARD",],buffer_frame:Some(10),hold_frame:Some(60),grace_frame:Some(3),direction_class:true,),)"#
+        );
+        assert_eq!(ron::de::from_str::<Step>(&ser).unwrap(), step);
+
+        // 省略したフィールドは None
+        let step: Step =
+            ron::de::from_str(r#"(key: [], command_key: Push(key: ["DOWN"]))"#).unwrap();
+        assert_eq!(
+            step.command_key,
+            CommandKey::Push {
+                key: Key::DOWN,
+                buffer_frame: None
+            }
+        );
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Move {
+        #[serde(with = "command")]
+        command: crate::Command,
+    }
+
+    #[test]
+    fn structured_command() {
+        let command = Move {
+            command: build_command("c4(45)[10] > p6 > pA @ 30").unwrap(),
+        };
+        let ser = ron::ser::to_string(&command).unwrap();
+        assert_eq!(
+            ser,
+            r#"(command:(keys:[Hold(key:["BACKWARD",],buffer_frame:Some(10),hold_frame:Some(45),direction_class:true,),Push(key:["FORWARD",],),Push(key:["A",],),],max_duration:Some(30),),)"#
+        );
+        assert_eq!(ron::de::from_str::<Move>(&ser).unwrap(), command);
+
+        let ser = serde_json::to_string(&command).unwrap();
+        assert_eq!(
+            ser,
+            r#"{"command":{"keys":[{"Hold":{"key":["BACKWARD"],"buffer_frame":10,"hold_frame":45,"direction_class":true}},{"Push":{"key":["FORWARD"]}},{"Push":{"key":["A"]}}],"max_duration":30}}"#
+        );
+        assert_eq!(serde_json::from_str::<Move>(&ser).unwrap(), command);
+    }
+
+    #[test]
+    fn structured_json() {
+        let step = Step {
+            key: Key::empty(),
+            command_key: *build_command("pB*4{30}").unwrap().keys().next().unwrap(),
+        };
+        let ser = serde_json::to_string(&step).unwrap();
+        assert_eq!(
+            ser,
+            r#"{"key":[],"command_key":{"Mash":{"key":["B"],"count":4,"window_frame":30}}}"#
+        );
+        assert_eq!(serde_json::from_str::<Step>(&ser).unwrap(), step);
+
+        serde_json::from_str::<Step>(r#"{"key":["E"],"command_key":{"On":{"key":["A"]}}}"#)
+            .unwrap_err();
+    }
+}
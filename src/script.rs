@@ -0,0 +1,145 @@
+// テストやログ用のフレームごとの入力の短い表記
+// 入力[x フレーム数] を空白区切りで並べる (入力なしは -)
+// 5x10 4x120 5x9 6x10 6C 5x9
+use crate::{
+    error::{Error, ParseError},
+    types::{build_key, button::Key},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct InputScript {
+    inputs: Vec<Key>,
+}
+
+impl InputScript {
+    pub fn parse(script: &str) -> Result<Self, Error> {
+        let mut inputs = vec![];
+        for token in script.split_whitespace() {
+            // フレーム数は1以上 (x0 や負の数は不可)
+            let (key, count) = match token.find('x') {
+                Some(i) => {
+                    let count = token[i + 1..]
+                        .parse::<u32>()
+                        .map_err(|err| ParseError::InvalidNumber { err })?;
+                    if count == 0 {
+                        return Err(ParseError::NotComplete { rest: token.into() }.into());
+                    }
+                    (&token[..i], count)
+                }
+                None => (token, 1),
+            };
+            let key = match key {
+                "-" => Key::empty(),
+                "" => return Err(ParseError::NotComplete { rest: token.into() }.into()),
+                key => build_key(key)?,
+            };
+            inputs.extend((0..count).map(|_| key));
+        }
+        Ok(InputScript { inputs })
+    }
+
+    pub fn inputs(&self) -> &[Key] {
+        &self.inputs
+    }
+
+    pub fn into_inputs(self) -> Vec<Key> {
+        self.inputs
+    }
+}
+
+impl From<Vec<Key>> for InputScript {
+    fn from(inputs: Vec<Key>) -> Self {
+        InputScript { inputs }
+    }
+}
+
+impl IntoIterator for InputScript {
+    type Item = Key;
+    type IntoIter = std::vec::IntoIter<Key>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inputs.into_iter()
+    }
+}
+
+impl std::str::FromStr for InputScript {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InputScript::parse(s)
+    }
+}
+
+// 同じ入力が続く分はまとめる
+impl std::fmt::Display for InputScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut rest = &self.inputs[..];
+        while let Some(&key) = rest.first() {
+            let count = rest.iter().take_while(|&&k| k == key).count();
+            if rest.len() != self.inputs.len() {
+                f.write_str(" ")?;
+            }
+            if key.is_empty() {
+                f.write_str("-")?;
+            } else {
+                write!(f, "{}", key)?;
+            }
+            if count > 1 {
+                write!(f, "x{}", count)?;
+            }
+            rest = &rest[count..];
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let script = InputScript::parse("5x2 4x3 - 6C").unwrap();
+        assert_eq!(
+            script.inputs(),
+            &[
+                Key::NEUTRAL,
+                Key::NEUTRAL,
+                Key::BACKWARD,
+                Key::BACKWARD,
+                Key::BACKWARD,
+                Key::empty(),
+                Key::FORWARD | Key::C,
+            ][..]
+        );
+        assert!(InputScript::parse("").unwrap().inputs().is_empty());
+
+        InputScript::parse("6xA").unwrap_err();
+        InputScript::parse("x3").unwrap_err();
+        InputScript::parse("6E").unwrap_err();
+    }
+
+    #[test]
+    fn parse_invalid_count() {
+        InputScript::parse("6x-3 5").unwrap_err();
+        InputScript::parse("6x+").unwrap_err();
+        assert!(matches!(
+            InputScript::parse("6x0"),
+            Err(Error::Parse(ParseError::NotComplete { .. }))
+        ));
+        assert!(matches!(
+            InputScript::parse("6x-3"),
+            Err(Error::Parse(ParseError::InvalidNumber { .. }))
+        ));
+    }
+
+    #[test]
+    fn display() {
+        let script = "5x10 4x120 5x9 6x10 6C 5x9";
+        assert_eq!(InputScript::parse(script).unwrap().to_string(), script);
+
+        let script = InputScript::from(vec![Key::empty(), Key::empty(), Key::DOWN | Key::A]);
+        assert_eq!(script.to_string(), "-x2 2A");
+        assert_eq!(InputScript::parse(&script.to_string()).unwrap(), script);
+    }
+}
@@ -0,0 +1,433 @@
+// 空白やコメントを含めて元の記法を復元できる構文木と整形
+use crate::{
+    error::{Error, ParseError},
+    types::{check_syntax, comment},
+};
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    // p, r, h, c, n, f, tap, dash
+    Prefix,
+    // 6A
+    Buttons,
+    Number,
+    // $qcf
+    Macro,
+    // >
+    Sequence,
+    // @
+    Duration,
+    // ( ) , [ ] { } *
+    Punct,
+    Whitespace,
+    Comment,
+}
+
+impl TokenKind {
+    pub fn is_trivia(self) -> bool {
+        self == TokenKind::Whitespace || self == TokenKind::Comment
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    // 元の文字列でのバイト位置
+    pub span: Range<usize>,
+    pub text: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxNode<'a> {
+    // 1つのキー入力または断片の参照: p6 [10]
+    Step(Vec<Token<'a>>),
+    // >
+    Sequence(Token<'a>),
+    // @ 30
+    Duration(Vec<Token<'a>>),
+    // キー入力の間の空白とコメント
+    Trivia(Token<'a>),
+}
+
+impl<'a> SyntaxNode<'a> {
+    pub fn tokens(&self) -> &[Token<'a>] {
+        match self {
+            SyntaxNode::Step(tokens) | SyntaxNode::Duration(tokens) => tokens,
+            SyntaxNode::Sequence(token) | SyntaxNode::Trivia(token) => std::slice::from_ref(token),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree<'a> {
+    source: &'a str,
+    nodes: Vec<SyntaxNode<'a>>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    pub fn parse(source: &'a str) -> Result<Self, Error> {
+        check_syntax(source)?;
+        let tokens = tokenize(source)?;
+        Ok(SyntaxTree {
+            source,
+            nodes: group(tokens),
+        })
+    }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn nodes(&self) -> &[SyntaxNode<'a>] {
+        &self.nodes
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = &Token<'a>> {
+        self.nodes.iter().flat_map(|node| node.tokens())
+    }
+}
+
+// トークンをつなげると元の文字列になる
+impl<'a> std::fmt::Display for SyntaxTree<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for token in self.tokens() {
+            f.write_str(token.text)?;
+        }
+        Ok(())
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token<'_>>, Error> {
+    let mut tokens = vec![];
+    let mut pos = 0;
+    // キー入力の種類の直後はボタン，それ以外の数字は数値
+    let mut expect_buttons = false;
+    while pos < source.len() {
+        let rest = &source[pos..];
+        let c = rest.chars().next().unwrap_or_default();
+        let (kind, len) = if c.is_whitespace() {
+            let len = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            (TokenKind::Whitespace, len)
+        } else if let Ok((_, comment)) = comment(rest) {
+            // コメントは解析と同じ規則で読む
+            (TokenKind::Comment, comment.len())
+        } else if rest.starts_with("tap") || rest.starts_with("dash") {
+            (TokenKind::Prefix, if c == 't' { 3 } else { 4 })
+        } else if "prhcnf".contains(c) {
+            (TokenKind::Prefix, 1)
+        } else if expect_buttons && "123456789ABCD".contains(c) {
+            let len = rest
+                .find(|c| !"123456789ABCD".contains(c))
+                .unwrap_or(rest.len());
+            (TokenKind::Buttons, len)
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            (TokenKind::Number, len)
+        } else if c == '$' {
+            let len = rest[1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map(|l| l + 1)
+                .unwrap_or(rest.len());
+            (TokenKind::Macro, len)
+        } else if c == '>' {
+            (TokenKind::Sequence, 1)
+        } else if c == '@' {
+            (TokenKind::Duration, 1)
+        } else if "(),[]{}*".contains(c) {
+            (TokenKind::Punct, 1)
+        } else {
+            return Err(ParseError::NotComplete { rest: rest.into() }.into());
+        };
+
+        if !kind.is_trivia() {
+            expect_buttons = kind == TokenKind::Prefix;
+        }
+        tokens.push(Token {
+            kind,
+            span: pos..pos + len,
+            text: &source[pos..pos + len],
+        });
+        pos += len;
+    }
+
+    Ok(tokens)
+}
+
+// キー入力ごとにまとめる
+// キー入力の後ろの空白とコメントはキー入力に含めない
+fn group(tokens: Vec<Token<'_>>) -> Vec<SyntaxNode<'_>> {
+    let mut nodes = vec![];
+    let mut current: Option<SyntaxNode> = None;
+    let mut trailing = vec![];
+    for token in tokens {
+        match token.kind {
+            TokenKind::Sequence => {
+                nodes.extend(current.take());
+                nodes.extend(trailing.drain(..).map(SyntaxNode::Trivia));
+                nodes.push(SyntaxNode::Sequence(token));
+            }
+            TokenKind::Duration => {
+                nodes.extend(current.take());
+                nodes.extend(trailing.drain(..).map(SyntaxNode::Trivia));
+                current = Some(SyntaxNode::Duration(vec![token]));
+            }
+            _ if token.kind.is_trivia() => {
+                if current.is_some() {
+                    trailing.push(token);
+                } else {
+                    nodes.push(SyntaxNode::Trivia(token));
+                }
+            }
+            _ => match &mut current {
+                Some(SyntaxNode::Step(tokens)) | Some(SyntaxNode::Duration(tokens)) => {
+                    tokens.append(&mut trailing);
+                    tokens.push(token);
+                }
+                _ => current = Some(SyntaxNode::Step(vec![token])),
+            },
+        }
+    }
+    nodes.extend(current);
+    nodes.extend(trailing.into_iter().map(SyntaxNode::Trivia));
+
+    nodes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatStyle {
+    // 1行: h4(60)[10] > p6[10] > pC
+    Compact,
+    // キー入力ごとに改行
+    // h4(60)[10]
+    // > p6[10]
+    // > pC
+    Pretty,
+}
+
+// 空白を整えた記法を返す
+// コメントは残す
+pub fn format_command(input: &str, style: FormatStyle) -> Result<String, Error> {
+    let tree = SyntaxTree::parse(input)?;
+    let mut writer = Writer::default();
+    for node in tree.nodes() {
+        match node {
+            SyntaxNode::Step(tokens) => {
+                writer.space();
+                for token in tokens {
+                    writer.token(token);
+                }
+            }
+            SyntaxNode::Sequence(token) => {
+                if style == FormatStyle::Pretty {
+                    writer.newline();
+                }
+                writer.space();
+                writer.push(token.text);
+                writer.space();
+            }
+            SyntaxNode::Duration(tokens) => {
+                writer.space();
+                for token in tokens {
+                    writer.space();
+                    writer.token(token);
+                }
+            }
+            SyntaxNode::Trivia(token) => writer.token(token),
+        }
+    }
+
+    Ok(writer.output)
+}
+
+#[derive(Default)]
+struct Writer {
+    output: String,
+    // 次の出力の前に空白を入れる
+    space: bool,
+}
+
+impl Writer {
+    fn push(&mut self, text: &str) {
+        if self.space && !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push(' ');
+        }
+        self.space = false;
+        self.output.push_str(text);
+    }
+
+    fn space(&mut self) {
+        self.space = true;
+    }
+
+    fn newline(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with('\n') {
+            self.output.push('\n');
+        }
+        self.space = false;
+    }
+
+    fn token(&mut self, token: &Token) {
+        match token.kind {
+            TokenKind::Whitespace => {}
+            TokenKind::Comment => {
+                self.space();
+                self.push(token.text.trim_end());
+                if token.text.starts_with("/*") {
+                    self.space();
+                } else {
+                    // 行コメントのあとは改行が必要
+                    self.newline();
+                }
+            }
+            _ => self.push(token.text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::build_command;
+    use proptest::prelude::*;
+
+    const COMMAND: &str = r#"# 溜め
+h 4 (60)[ 8 ] // 後ろ
+        > r 6 [10 ]
+    > p C6 /* 同時 */ [ 20]@ 90"#;
+
+    #[test]
+    fn lossless() {
+        let tree = SyntaxTree::parse(COMMAND).unwrap();
+        assert_eq!(tree.to_string(), COMMAND);
+        let steps = tree
+            .nodes()
+            .iter()
+            .filter(|node| matches!(node, SyntaxNode::Step(_)))
+            .count();
+        assert_eq!(steps, 3);
+        let comments = tree
+            .tokens()
+            .filter(|token| token.kind == TokenKind::Comment)
+            .map(|token| &COMMAND[token.span.clone()])
+            .collect::<Vec<_>>();
+        assert_eq!(comments, vec!["# 溜め", "// 後ろ", "/* 同時 */"]);
+
+        SyntaxTree::parse("p6 > pX").unwrap_err();
+    }
+
+    #[test]
+    fn block_comment_opener() {
+        // 開始の /* の * は終わりの */ に含めない
+        let source = "p6 /*/ x */ > pA";
+        let tree = SyntaxTree::parse(source).unwrap();
+        let comments = tree
+            .tokens()
+            .filter(|token| token.kind == TokenKind::Comment)
+            .map(|token| token.text)
+            .collect::<Vec<_>>();
+        assert_eq!(comments, vec!["/*/ x */"]);
+        assert_eq!(tree.to_string(), source);
+    }
+
+    fn trivia() -> impl Strategy<Value = String> {
+        let piece = prop::sample::select(vec![
+            " ",
+            "\n",
+            "# c\n",
+            "// c\n",
+            "/* c */",
+            "/*/ x */",
+            "/**/",
+            "/* // */",
+            "/* # > */",
+            "/* * / */",
+            "/***/",
+        ]);
+        prop::collection::vec(piece, 0..3).prop_map(|pieces| pieces.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn tokens_agree_with_parser(
+            steps in prop::collection::vec(
+                (
+                    prop::sample::select(vec!["p6", "h4(30)", "r2[5]", "pA*3{20}", "dash6", "c2(10,2)"]),
+                    trivia(),
+                    trivia(),
+                ),
+                1..5,
+            ),
+            head in trivia(),
+        ) {
+            let mut source = head;
+            for (i, (step, before, after)) in steps.iter().enumerate() {
+                if i > 0 {
+                    source += ">";
+                }
+                source += before;
+                source += step;
+                source += after;
+            }
+            prop_assert!(build_command(&source).is_ok(), "{}", source);
+            let tree = SyntaxTree::parse(&source).unwrap();
+            prop_assert_eq!(tree.to_string(), source.clone());
+
+            // 空白とコメント以外のトークンをつなげると元のキー入力になる
+            let text = tree
+                .tokens()
+                .filter(|token| !token.kind.is_trivia())
+                .map(|token| token.text)
+                .collect::<String>();
+            let expected = steps
+                .iter()
+                .map(|(step, _, _)| *step)
+                .collect::<Vec<_>>()
+                .join(">");
+            prop_assert_eq!(text, expected);
+        }
+    }
+
+    #[test]
+    fn format_compact() {
+        let formatted = format_command(COMMAND, FormatStyle::Compact).unwrap();
+        assert_eq!(
+            formatted,
+            "# 溜め\nh4(60)[8] // 後ろ\n> r6[10] > pC6 /* 同時 */ [20] @ 90"
+        );
+        let formatted = format_command(
+            "h 4 (60)[ 8 ]\n> r 6 [10 ]\n> p C6 [ 20]",
+            FormatStyle::Compact,
+        )
+        .unwrap();
+        assert_eq!(formatted, "h4(60)[8] > r6[10] > pC6[20]");
+    }
+
+    #[test]
+    fn format_pretty() {
+        let formatted = format_command(COMMAND, FormatStyle::Pretty).unwrap();
+        assert_eq!(
+            formatted,
+            "# 溜め\nh4(60)[8] // 後ろ\n> r6[10]\n> pC6 /* 同時 */ [20] @ 90"
+        );
+        let formatted = format_command("$qcf>pB * 4 {30}>c4(60 , 3)", FormatStyle::Pretty).unwrap();
+        assert_eq!(formatted, "$qcf\n> pB*4{30}\n> c4(60,3)");
+    }
+
+    #[test]
+    fn format_keeps_command() {
+        // 整形しても同じコマンドになり，もう一度整形しても変わらない
+        for style in &[FormatStyle::Compact, FormatStyle::Pretty] {
+            let formatted = format_command(COMMAND, *style).unwrap();
+            assert_eq!(
+                build_command(&formatted).unwrap(),
+                build_command(COMMAND).unwrap()
+            );
+            assert_eq!(format_command(&formatted, *style).unwrap(), formatted);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    command::Command,
+    types::{
+        button::Key,
+        input::{dash_keys, CommandKey},
+    },
+};
+
+// 前ダッシュ: dash6[buffer_frame]
+pub fn forward_dash(buffer_frame: u32) -> Command {
+    Command::new(dash_keys(Key::FORWARD, Some(buffer_frame)))
+}
+
+// バックステップ: dash4[buffer_frame]
+pub fn back_dash(buffer_frame: u32) -> Command {
+    Command::new(dash_keys(Key::BACKWARD, Some(buffer_frame)))
+}
+
+// ハイジャンプ: p2[buffer_frame] > p8
+pub fn super_jump(buffer_frame: u32) -> Command {
+    Command::new([
+        CommandKey::Push {
+            key: Key::DOWN,
+            buffer_frame: Some(buffer_frame),
+        },
+        CommandKey::Push {
+            key: Key::UP,
+            buffer_frame: None,
+        },
+    ])
+}
+
+// テストは記法の解析を使うので std のときだけ
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::types::build_command;
+
+    #[test]
+    fn same_as_notation() {
+        assert_eq!(forward_dash(12), build_command("dash6[12]").unwrap());
+        assert_eq!(back_dash(12), build_command("dash4[12]").unwrap());
+        assert_eq!(super_jump(8), build_command("p2[8] > p8").unwrap());
+    }
+}
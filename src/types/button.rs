@@ -1,4 +1,5 @@
-use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::error::ParseError;
 
 bitflags::bitflags! {
     pub struct Key : u16 {
@@ -21,8 +22,69 @@ bitflags::bitflags! {
     }
 }
 
+impl Key {
+    pub const BUTTONS: Key =
+        Key::from_bits_truncate(Key::A.bits() | Key::B.bits() | Key::C.bits() | Key::D.bits());
+    // ニュートラルを除くすべての方向
+    pub const DIRECTIONS: Key = Key::from_bits_truncate(
+        Key::FORWARD.bits()
+            | Key::BACKWARD.bits()
+            | Key::UP.bits()
+            | Key::DOWN.bits()
+            | Key::FD.bits()
+            | Key::FU.bits()
+            | Key::BD.bits()
+            | Key::BU.bits(),
+    );
+
+    // 方向成分ごとの入力の分類
+    // 4 なら 1, 4, 7 のように斜めも含める
+    pub fn direction_class(self) -> Key {
+        let mut class = self;
+        if self.contains(Key::FORWARD) {
+            class |= Key::FU | Key::FD;
+        }
+        if self.contains(Key::BACKWARD) {
+            class |= Key::BU | Key::BD;
+        }
+        if self.contains(Key::UP) {
+            class |= Key::FU | Key::BU;
+        }
+        if self.contains(Key::DOWN) {
+            class |= Key::FD | Key::BD;
+        }
+        class
+    }
+
+    // 方向の分類を考慮して入力が含まれているか
+    // ボタンは全て，方向はいずれかを含んでいればよい
+    pub fn contains_class(self, key: Key) -> bool {
+        let buttons = key & Key::BUTTONS;
+        let directions = key - Key::BUTTONS;
+        self.contains(buttons)
+            && (directions.is_empty() || self.intersects(directions.direction_class()))
+    }
+
+    // 左右を入れ替える
+    // 左向きのときの絶対方向と前後の変換に使う
+    pub fn mirrored(self) -> Key {
+        let mut key = self - (Key::FORWARD | Key::BACKWARD | Key::FU | Key::FD | Key::BU | Key::BD);
+        let swap = [
+            (Key::FORWARD, Key::BACKWARD),
+            (Key::FU, Key::BU),
+            (Key::FD, Key::BD),
+        ];
+        for &(a, b) in swap.iter() {
+            key.set(b, self.contains(a));
+            key.set(a, self.contains(b));
+        }
+        key
+    }
+}
+
+#[cfg(feature = "std")]
 impl std::str::FromStr for Key {
-    type Err = Error;
+    type Err = ParseError;
 
     fn from_str(button: &str) -> Result<Key, Self::Err> {
         match button {
@@ -39,13 +101,13 @@ impl std::str::FromStr for Key {
             "7" => Ok(Key::BU),
             "8" => Ok(Key::UP),
             "9" => Ok(Key::FU),
-            _ => Err(Error::UnKnownKeyName),
+            _ => Err(ParseError::UnknownKeyName),
         }
     }
 }
 
-impl std::fmt::Display for Key {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for Key {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         if self.contains(Key::FORWARD) {
             f.write_str("6")?;
         }
@@ -91,3 +153,34 @@ impl std::fmt::Display for Key {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direction_class() {
+        assert_eq!(
+            Key::BACKWARD.direction_class(),
+            Key::BACKWARD | Key::BD | Key::BU
+        );
+        assert_eq!(Key::BD.direction_class(), Key::BD);
+        assert!(Key::BD.contains_class(Key::BACKWARD));
+        assert!(Key::BD.contains_class(Key::DOWN));
+        assert!(!Key::FD.contains_class(Key::BACKWARD));
+        assert!((Key::BU | Key::A).contains_class(Key::BACKWARD | Key::A));
+        assert!(!Key::BU.contains_class(Key::BACKWARD | Key::A));
+    }
+
+    #[test]
+    fn mirrored() {
+        assert_eq!(Key::FORWARD.mirrored(), Key::BACKWARD);
+        assert_eq!((Key::FD | Key::A).mirrored(), Key::BD | Key::A);
+        assert_eq!(Key::BU.mirrored(), Key::FU);
+        assert_eq!(Key::DOWN.mirrored(), Key::DOWN);
+        assert_eq!(
+            (Key::FORWARD | Key::BACKWARD).mirrored(),
+            Key::FORWARD | Key::BACKWARD
+        );
+    }
+}
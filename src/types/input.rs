@@ -1,6 +1,6 @@
-use crate::{error::Error, types::button::Key};
+use crate::types::button::Key;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CommandKey {
     // 押したときのトリガー
     Push {
@@ -15,60 +15,64 @@ pub enum CommandKey {
         key: Key,
         buffer_frame: Option<u32>,
         hold_frame: Option<u32>,
+        // タメが途切れても許容するフレーム数
+        grace_frame: Option<u32>,
+        // 斜めも含めた方向の分類でタメを判定する
+        direction_class: bool,
     },
     On {
         key: Key,
     },
+    // キーを全ては押していない
+    // 5 を含むときはスティックがニュートラルに戻っている
     Off {
         key: Key,
     },
+    // 連打: window_frame 内に count 回押す
+    Mash {
+        key: Key,
+        count: u32,
+        window_frame: Option<u32>,
+    },
 }
 
-pub(crate) fn to_push_command_key(
-    (key, buffer_frame): (Key, Option<&str>),
-) -> Result<CommandKey, Error> {
-    let buffer_frame = match buffer_frame {
-        Some(f) => Some(f.parse().map_err(|err| Error::IntParseError { err })?),
-        None => None,
-    };
-
-    Ok(CommandKey::Push { key, buffer_frame })
-}
-
-pub(crate) fn to_release_command_key(
-    (key, buffer_frame): (Key, Option<&str>),
-) -> Result<CommandKey, Error> {
-    let buffer_frame = match buffer_frame {
-        Some(f) => Some(f.parse().map_err(|err| Error::IntParseError { err })?),
-        None => None,
-    };
-
-    Ok(CommandKey::Release { key, buffer_frame })
+impl CommandKey {
+    pub fn key(&self) -> Key {
+        match *self {
+            CommandKey::Push { key, .. }
+            | CommandKey::Release { key, .. }
+            | CommandKey::Hold { key, .. }
+            | CommandKey::On { key }
+            | CommandKey::Off { key }
+            | CommandKey::Mash { key, .. } => key,
+        }
+    }
 }
 
-pub(crate) fn to_hold_command_key(
-    (key, (hold_frame, buffer_frame)): (Key, (Option<&str>, Option<&str>)),
-) -> Result<CommandKey, Error> {
-    let buffer_frame = match buffer_frame {
-        Some(f) => Some(f.parse().map_err(|err| Error::IntParseError { err })?),
-        None => None,
-    };
-    let hold_frame = match hold_frame {
-        Some(f) => Some(f.parse().map_err(|err| Error::IntParseError { err })?),
-        None => None,
+// 押して離す: p > f
+// 方向は別の方向への入れ替えを離しとみなさないよう，ニュートラルに戻す
+pub(crate) fn tap_keys(key: Key, buffer_frame: Option<u32>) -> [CommandKey; 2] {
+    let released = if key.intersects(Key::DIRECTIONS) {
+        (key & Key::BUTTONS) | Key::NEUTRAL
+    } else {
+        key
     };
-
-    Ok(CommandKey::Hold {
-        key,
-        hold_frame,
-        buffer_frame,
-    })
-}
-
-pub(crate) fn to_on_key(key: Key) -> Result<CommandKey, Error> {
-    Ok(CommandKey::On { key })
+    [
+        CommandKey::Push { key, buffer_frame },
+        CommandKey::Off { key: released },
+    ]
 }
 
-pub(crate) fn to_off_key(key: Key) -> Result<CommandKey, Error> {
-    Ok(CommandKey::Off { key })
+// 2回入力: p > f > p
+// バッファは1回目の押しから2回目の押しまでの猶予
+pub(crate) fn dash_keys(key: Key, buffer_frame: Option<u32>) -> [CommandKey; 3] {
+    let [push, off] = tap_keys(key, buffer_frame);
+    [
+        push,
+        off,
+        CommandKey::Push {
+            key,
+            buffer_frame: None,
+        },
+    ]
 }
@@ -0,0 +1,759 @@
+// 記法の解析
+use super::{
+    button::Key,
+    input::{self, CommandKey},
+};
+use crate::{
+    command::Command,
+    error::{Error, ParseError},
+    validate::Severity,
+};
+use nom::{
+    branch::*, bytes::complete::*, character::complete::*, combinator::*, multi::*, sequence::*,
+    IResult,
+};
+use std::collections::BTreeMap;
+
+// 名前付きの記法の断片: qcf => "p2 > p3 > p6"
+pub type Macros = BTreeMap<String, String>;
+
+// 記法の要素
+enum Item<'a> {
+    Keys(Vec<CommandKey>),
+    // $qcf
+    Macro(&'a str),
+}
+
+pub fn build_command(input: &str) -> Result<Command, Error> {
+    build_command_with_macros(input, &Macros::new())
+}
+
+// $名前 を展開しながら変換
+pub fn build_command_with_macros(input: &str, macros: &Macros) -> Result<Command, Error> {
+    let (items, max_duration) = parse_items(input)?;
+    let keys = expand_macros(items, macros, &mut vec![])?;
+
+    build_command_from_keys(keys, max_duration)
+}
+
+// 断片として展開できるか検証
+pub(crate) fn check_macro(name: &str, macros: &Macros) -> Result<(), Error> {
+    expand_macros(vec![Item::Macro(name)], macros, &mut vec![]).map(|_| ())
+}
+
+// 断片を展開せずに記法として正しいか検証
+pub(crate) fn check_syntax(input: &str) -> Result<(), Error> {
+    parse_items(input).map(|_| ())
+}
+
+fn parse_items(input: &str) -> Result<(Vec<Item<'_>>, Option<u32>), Error> {
+    let (rest, (_, items, max_duration, _)) = tuple((
+        trivia,
+        separated_list(
+            sequence,
+            alt((
+                map(tap_keys, Item::Keys),
+                map(dash_keys, Item::Keys),
+                map(command_key, |key| Item::Keys(vec![key])),
+                map(macro_name, Item::Macro),
+            )),
+        ),
+        opt(max_duration),
+        trivia,
+    ))(input)
+    .map_err(|_| ParseError::Syntax {
+        string: input.into(),
+    })?;
+
+    if !rest.is_empty() {
+        return Err(ParseError::NotComplete { rest: rest.into() }.into());
+    }
+
+    let max_duration = match max_duration {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+
+    Ok((items, max_duration))
+}
+
+// stack は展開中の名前 (循環の検出用)
+fn expand_macros<'a>(
+    items: Vec<Item<'a>>,
+    macros: &'a Macros,
+    stack: &mut Vec<&'a str>,
+) -> Result<Vec<CommandKey>, Error> {
+    let mut keys = vec![];
+    for item in items {
+        match item {
+            Item::Keys(k) => keys.extend(k),
+            Item::Macro(name) => {
+                if stack.contains(&name) {
+                    let mut chain = stack.clone();
+                    chain.push(name);
+                    return Err(ParseError::RecursiveMacro {
+                        name: name.into(),
+                        chain: chain.join(" -> "),
+                    }
+                    .into());
+                }
+                let (name, fragment) = macros
+                    .get_key_value(name)
+                    .ok_or_else(|| ParseError::UndefinedMacro { name: name.into() })?;
+                let (items, max_duration) =
+                    parse_items(fragment).map_err(|err| ParseError::InvalidMacro {
+                        name: name.clone(),
+                        reason: err.to_string(),
+                    })?;
+                // 制限時間はコマンド全体にのみ指定できる
+                if max_duration.is_some() {
+                    return Err(ParseError::InvalidMacro {
+                        name: name.clone(),
+                        reason: "max duration is not allowed in macro".into(),
+                    }
+                    .into());
+                }
+                stack.push(name);
+                keys.extend(expand_macros(items, macros, stack)?);
+                stack.pop();
+            }
+        }
+    }
+
+    Ok(keys)
+}
+
+// 記法から変換したものと構造化した形式から変換したものを同じく検証する
+pub(crate) fn build_command_from_keys(
+    keys: Vec<CommandKey>,
+    max_duration: Option<u32>,
+) -> Result<Command, Error> {
+    if let Some(index) = keys.iter().position(|key| key.key().is_empty()) {
+        return Err(ParseError::EmptyKey { index }.into());
+    }
+
+    // 判定できないコマンドはエラー
+    let command = Command::try_new(keys)?.with_max_duration(max_duration);
+    if let Some(diagnostic) = command
+        .validate()
+        .into_iter()
+        .find(|d| d.severity() == Severity::Error)
+    {
+        return Err(diagnostic.into());
+    }
+
+    Ok(command)
+}
+
+// 単体のキー入力: h4(60)[10]
+pub(crate) fn build_command_key(input: &str) -> Result<CommandKey, Error> {
+    let (rest, (_, key, _)) =
+        tuple((trivia, command_key, trivia))(input).map_err(|_| ParseError::Syntax {
+            string: input.into(),
+        })?;
+
+    if !rest.is_empty() {
+        return Err(ParseError::NotComplete { rest: rest.into() }.into());
+    }
+
+    Ok(key)
+}
+
+// キーの組み合わせ: 6A
+// 空文字は入力なし
+pub(crate) fn build_key(input: &str) -> Result<Key, Error> {
+    if input.is_empty() {
+        return Ok(Key::empty());
+    }
+    let (rest, key) = buttons(input).map_err(|_| ParseError::Syntax {
+        string: input.into(),
+    })?;
+
+    if !rest.is_empty() {
+        return Err(ParseError::NotComplete { rest: rest.into() }.into());
+    }
+
+    Ok(key)
+}
+
+fn command_key(input: &str) -> IResult<&str, CommandKey> {
+    alt((
+        hold_key,
+        charge_key,
+        mash_key,
+        push_key,
+        release_key,
+        on_key,
+        off_key,
+    ))(input)
+}
+
+fn button(input: &str) -> IResult<&str, Key> {
+    map_res(
+        alt((tag("A"), tag("B"), tag("C"), tag("D"))),
+        std::str::FromStr::from_str,
+    )(input)
+}
+
+fn stick(input: &str) -> IResult<&str, Key> {
+    map_res(
+        alt((
+            tag("1"),
+            tag("2"),
+            tag("3"),
+            tag("4"),
+            tag("5"),
+            tag("6"),
+            tag("7"),
+            tag("8"),
+            tag("9"),
+        )),
+        std::str::FromStr::from_str,
+    )(input)
+}
+
+fn buffer_start(input: &str) -> IResult<&str, &str> {
+    tag("[")(input)
+}
+
+fn buffer_end(input: &str) -> IResult<&str, &str> {
+    tag("]")(input)
+}
+
+fn hold_start(input: &str) -> IResult<&str, &str> {
+    tag("(")(input)
+}
+
+fn hold_end(input: &str) -> IResult<&str, &str> {
+    tag(")")(input)
+}
+
+fn grace_start(input: &str) -> IResult<&str, &str> {
+    tag(",")(input)
+}
+
+fn window_start(input: &str) -> IResult<&str, &str> {
+    tag("{")(input)
+}
+
+fn window_end(input: &str) -> IResult<&str, &str> {
+    tag("}")(input)
+}
+
+fn repeat(input: &str) -> IResult<&str, &str> {
+    tag("*")(input)
+}
+
+fn duration_start(input: &str) -> IResult<&str, &str> {
+    tag("@")(input)
+}
+
+// 空白とコメント
+// # コメント, // コメント, /* コメント */
+fn trivia(input: &str) -> IResult<&str, &str> {
+    recognize(many0(alt((multispace1, comment))))(input)
+}
+
+// 構文木の字句解析でも使う
+pub(crate) fn comment(input: &str) -> IResult<&str, &str> {
+    alt((line_comment, block_comment))(input)
+}
+
+fn line_comment(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        alt((tag("#"), tag("//"))),
+        take_till(|c| c == '\n'),
+    )))(input)
+}
+
+fn block_comment(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((tag("/*"), take_until("*/"), tag("*/"))))(input)
+}
+
+fn sequence(input: &str) -> IResult<&str, ()> {
+    let (rest, (_, _, _)) = tuple((trivia, tag(">"), trivia))(input)?;
+
+    Ok((rest, ()))
+}
+
+fn numbers(input: &str) -> IResult<&str, &str> {
+    digit1(input)
+}
+
+fn buttons(input: &str) -> IResult<&str, Key> {
+    fold_many1(alt((stick, button)), Key::empty(), |acc, b| acc | b)(input)
+}
+
+fn buffer_frame(input: &str) -> IResult<&str, &str> {
+    let (rest, (_, _, _, frame, _, _, _)) = tuple((
+        trivia,
+        buffer_start,
+        trivia,
+        numbers,
+        trivia,
+        buffer_end,
+        trivia,
+    ))(input)?;
+
+    Ok((rest, frame))
+}
+
+fn grace_frame(input: &str) -> IResult<&str, &str> {
+    let (rest, (_, _, frame, _)) = tuple((grace_start, trivia, numbers, trivia))(input)?;
+
+    Ok((rest, frame))
+}
+
+// タメ時間と途切れの猶予: (60) (60, 3) (, 3)
+fn hold_frame(input: &str) -> IResult<&str, (Option<&str>, Option<&str>)> {
+    let (rest, (_, _, _, frame, _, grace, _, _)) = tuple((
+        trivia,
+        hold_start,
+        trivia,
+        opt(numbers),
+        trivia,
+        opt(grace_frame),
+        hold_end,
+        trivia,
+    ))(input)?;
+
+    Ok((rest, (frame, grace)))
+}
+
+fn mash_count(input: &str) -> IResult<&str, &str> {
+    let (rest, (_, _, _, count, _)) = tuple((trivia, repeat, trivia, numbers, trivia))(input)?;
+
+    Ok((rest, count))
+}
+
+fn window_frame(input: &str) -> IResult<&str, &str> {
+    let (rest, (_, _, _, frame, _, _, _)) = tuple((
+        trivia,
+        window_start,
+        trivia,
+        numbers,
+        trivia,
+        window_end,
+        trivia,
+    ))(input)?;
+
+    Ok((rest, frame))
+}
+
+// コマンド全体の制限時間: ... @ 30
+fn max_duration(input: &str) -> IResult<&str, &str> {
+    let (rest, (_, _, _, frame)) = tuple((trivia, duration_start, trivia, numbers))(input)?;
+
+    Ok((rest, frame))
+}
+
+// 名前付きの断片: $qcf
+fn macro_name(input: &str) -> IResult<&str, &str> {
+    let (rest, (_, name, _)) = tuple((
+        tag("$"),
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+        trivia,
+    ))(input)?;
+
+    Ok((rest, name))
+}
+
+fn push_key(input: &str) -> IResult<&str, CommandKey> {
+    let (rest, _) = tuple((tag("p"), trivia))(input)?;
+    let (rest, command) = map_res(tuple((buttons, opt(buffer_frame))), to_push_command_key)(rest)?;
+
+    Ok((rest, command))
+}
+
+// 連打: pA*4{30}
+fn mash_key(input: &str) -> IResult<&str, CommandKey> {
+    let (rest, _) = tuple((tag("p"), trivia))(input)?;
+    let (rest, command) = map_res(
+        tuple((buttons, tuple((mash_count, opt(window_frame))))),
+        to_mash_command_key,
+    )(rest)?;
+
+    Ok((rest, command))
+}
+
+fn release_key(input: &str) -> IResult<&str, CommandKey> {
+    let (rest, _) = tuple((tag("r"), trivia))(input)?;
+    let (rest, command) =
+        map_res(tuple((buttons, opt(buffer_frame))), to_release_command_key)(rest)?;
+
+    Ok((rest, command))
+}
+
+fn hold_key(input: &str) -> IResult<&str, CommandKey> {
+    let (rest, _) = tuple((tag("h"), trivia))(input)?;
+    let (rest, command) = map_res(
+        tuple((buttons, permutation((opt(hold_frame), opt(buffer_frame))))),
+        |hold| to_hold_command_key(hold, false),
+    )(rest)?;
+
+    Ok((rest, command))
+}
+
+// 斜めを含めたタメ: c4(60) は 1, 4, 7 のいずれかでタメる
+fn charge_key(input: &str) -> IResult<&str, CommandKey> {
+    let (rest, _) = tuple((tag("c"), trivia))(input)?;
+    let (rest, command) = map_res(
+        tuple((buttons, permutation((opt(hold_frame), opt(buffer_frame))))),
+        |hold| to_hold_command_key(hold, true),
+    )(rest)?;
+
+    Ok((rest, command))
+}
+
+// タップ: tap6[10] => p6[10] > f5, tapA => pA > fA
+fn tap_keys(input: &str) -> IResult<&str, Vec<CommandKey>> {
+    let (rest, _) = tuple((tag("tap"), trivia))(input)?;
+    let (rest, command) = map_res(tuple((buttons, opt(buffer_frame))), to_tap_command_keys)(rest)?;
+
+    Ok((rest, command))
+}
+
+// ダッシュ: dash6[10] => p6[10] > f5 > p6
+fn dash_keys(input: &str) -> IResult<&str, Vec<CommandKey>> {
+    let (rest, _) = tuple((tag("dash"), trivia))(input)?;
+    let (rest, command) = map_res(tuple((buttons, opt(buffer_frame))), to_dash_command_keys)(rest)?;
+
+    Ok((rest, command))
+}
+
+fn on_key(input: &str) -> IResult<&str, CommandKey> {
+    let (rest, _) = tuple((tag("n"), trivia))(input)?;
+    let (rest, command) = map_res(buttons, to_on_key)(rest)?;
+
+    Ok((rest, command))
+}
+
+fn off_key(input: &str) -> IResult<&str, CommandKey> {
+    let (rest, _) = tuple((tag("f"), trivia))(input)?;
+    let (rest, command) = map_res(buttons, to_off_key)(rest)?;
+
+    Ok((rest, command))
+}
+
+pub(crate) fn to_push_command_key(
+    (key, buffer_frame): (Key, Option<&str>),
+) -> Result<CommandKey, Error> {
+    let buffer_frame = match buffer_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+
+    Ok(CommandKey::Push { key, buffer_frame })
+}
+
+pub(crate) fn to_release_command_key(
+    (key, buffer_frame): (Key, Option<&str>),
+) -> Result<CommandKey, Error> {
+    let buffer_frame = match buffer_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+
+    Ok(CommandKey::Release { key, buffer_frame })
+}
+
+// タメ時間と途切れの猶予
+type HoldFrame<'a> = Option<(Option<&'a str>, Option<&'a str>)>;
+
+pub(crate) fn to_hold_command_key(
+    (key, (hold_frame, buffer_frame)): (Key, (HoldFrame, Option<&str>)),
+    direction_class: bool,
+) -> Result<CommandKey, Error> {
+    let buffer_frame = match buffer_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+    let (hold_frame, grace_frame) = hold_frame.unwrap_or((None, None));
+    let hold_frame = match hold_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+    let grace_frame = match grace_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+
+    Ok(CommandKey::Hold {
+        key,
+        hold_frame,
+        buffer_frame,
+        grace_frame,
+        direction_class,
+    })
+}
+
+pub(crate) fn to_mash_command_key(
+    (key, (count, window_frame)): (Key, (&str, Option<&str>)),
+) -> Result<CommandKey, Error> {
+    let count = count
+        .parse()
+        .map_err(|err| ParseError::InvalidNumber { err })?;
+    let window_frame = match window_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+
+    Ok(CommandKey::Mash {
+        key,
+        count,
+        window_frame,
+    })
+}
+
+pub(crate) fn to_on_key(key: Key) -> Result<CommandKey, Error> {
+    Ok(CommandKey::On { key })
+}
+
+pub(crate) fn to_off_key(key: Key) -> Result<CommandKey, Error> {
+    Ok(CommandKey::Off { key })
+}
+
+pub(crate) fn to_tap_command_keys(
+    (key, buffer_frame): (Key, Option<&str>),
+) -> Result<Vec<CommandKey>, Error> {
+    let buffer_frame = match buffer_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+
+    Ok(input::tap_keys(key, buffer_frame).to_vec())
+}
+
+pub(crate) fn to_dash_command_keys(
+    (key, buffer_frame): (Key, Option<&str>),
+) -> Result<Vec<CommandKey>, Error> {
+    let buffer_frame = match buffer_frame {
+        Some(f) => Some(f.parse().map_err(|err| ParseError::InvalidNumber { err })?),
+        None => None,
+    };
+
+    Ok(input::dash_keys(key, buffer_frame).to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn push_parse() {
+        push_key("pABC1234[100]").unwrap();
+    }
+    #[test]
+    fn hold_parse() {
+        hold_key("hABC1234(10)").unwrap();
+    }
+    #[test]
+    fn charge_parse() {
+        let (_, key) = charge_key("c4(60, 3)[10]").unwrap();
+        assert_eq!(
+            key,
+            CommandKey::Hold {
+                key: Key::BACKWARD,
+                buffer_frame: Some(10),
+                hold_frame: Some(60),
+                grace_frame: Some(3),
+                direction_class: true,
+            }
+        );
+        let (_, key) = hold_key("h2(,3)[10]").unwrap();
+        assert_eq!(
+            key,
+            CommandKey::Hold {
+                key: Key::DOWN,
+                buffer_frame: Some(10),
+                hold_frame: None,
+                grace_frame: Some(3),
+                direction_class: false,
+            }
+        );
+    }
+
+    #[test]
+    fn release_parse() {
+        release_key("rABC1234[100]").unwrap();
+    }
+
+    #[test]
+    fn mash_parse() {
+        let (_, key) = mash_key("pB * 4 {30}").unwrap();
+        assert_eq!(
+            key,
+            CommandKey::Mash {
+                key: Key::B,
+                count: 4,
+                window_frame: Some(30)
+            }
+        );
+        // 回数の無いものは通常の押し
+        let command = build_command("pB > pB*3").unwrap();
+        assert_eq!(command.keys().count(), 2);
+    }
+
+    #[test]
+    fn command_parse() {
+        // > の前後は半角スペース，タブ可
+        let commands = build_command("h4 >       r6 > pC").unwrap();
+        assert_eq!(commands.keys().count(), 3);
+    }
+
+    #[test]
+    fn command_parse_max_duration() {
+        let command = build_command("p2 > p3 > p6[8] > pA @ 30").unwrap();
+        assert_eq!(command.keys().count(), 4);
+        assert_eq!(command.max_duration(), Some(30));
+
+        let command = build_command("p2 > p3 > p6[8] > pA").unwrap();
+        assert_eq!(command.max_duration(), None);
+
+        // 制限時間は末尾のみ
+        build_command("p2 @ 30 > p3").unwrap_err();
+    }
+
+    #[test]
+    fn command_parse_macro() {
+        let macros = vec![
+            ("qcf", "p2 > p3 > p6"),
+            ("hcf", "p4 > p1 > $qcf"),
+            ("dp", "p6 > p2 > p3"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Macros>();
+
+        let command = build_command_with_macros("$qcf > pA", &macros).unwrap();
+        assert_eq!(command, build_command("p2 > p3 > p6 > pA").unwrap());
+
+        let command = build_command_with_macros("$hcf>pA @ 40", &macros).unwrap();
+        assert_eq!(
+            command,
+            build_command("p4 > p1 > p2 > p3 > p6 > pA @ 40").unwrap()
+        );
+
+        // 定義されていない名前
+        let err = build_command_with_macros("$qcb > pA", &macros).unwrap_err();
+        assert!(err.to_string().contains("qcb"), "{}", err);
+        build_command("$qcf > pA").unwrap_err();
+    }
+
+    #[test]
+    fn command_parse_macro_recursive() {
+        let macros = vec![("a", "p2 > $b"), ("b", "p6 > $a"), ("c", "$c")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Macros>();
+        let err = build_command_with_macros("$a > pA", &macros).unwrap_err();
+        assert!(err.to_string().contains("a -> b -> a"), "{}", err);
+        check_macro("c", &macros).unwrap_err();
+    }
+
+    #[test]
+    fn command_parse_space() {
+        // キー入力ボタン部分以外は半角スペース，タブ，改行を許容
+        build_command(
+            r#"h 4 (60)[ 8 ]
+            > r 6 [10 ]
+        > p C6 [ 20]"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn dash_parse() {
+        let command = build_command("dash6[12]").unwrap();
+        let keys = command.keys().copied().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                CommandKey::Push {
+                    key: Key::FORWARD,
+                    buffer_frame: Some(12)
+                },
+                CommandKey::Off { key: Key::NEUTRAL },
+                CommandKey::Push {
+                    key: Key::FORWARD,
+                    buffer_frame: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn tap_parse() {
+        // 他のキーと組み合わせ可能
+        let command = build_command("tap 4[8] > dash6 > pA").unwrap();
+        assert_eq!(command.keys().count(), 6);
+    }
+
+    #[test]
+    fn command_parse_comment() {
+        // 空白の代わりにコメントを書ける
+        let command = build_command(
+            r#"# 溜め
+            h4(60)[10] // 後ろ溜め
+            > p6 /* 前 */ [10]
+            > pC /* 最後に
+                    ボタン */ @ /* 全体 */ 90 # 制限時間"#,
+        )
+        .unwrap();
+        assert_eq!(
+            command,
+            build_command("h4(60)[10] > p6[10] > pC @ 90").unwrap()
+        );
+    }
+
+    #[test]
+    fn command_parse_comment_fail() {
+        // 閉じていないコメント
+        build_command("h4(60)[10] > p6 /* 前").unwrap_err();
+        // キー入力ボタン部分にはコメントを書けない
+        build_command("pC/* */6").unwrap_err();
+    }
+
+    #[test]
+    fn command_parse_invalid() {
+        // 判定できないコマンドは検証エラー
+        let err = build_command("h4(0)[10] > p6").unwrap_err();
+        assert!(err.to_string().contains("E001"), "{}", err);
+        build_command("p6[0]").unwrap_err();
+        build_command("pA*0").unwrap_err();
+        build_command("p46").unwrap_err();
+        build_command("p6 > pA @ 0").unwrap_err();
+
+        // 警告は許容
+        build_command("h5(60) > n6 > f6").unwrap();
+    }
+
+    #[test]
+    fn command_parse_too_many_keys() {
+        let command = vec!["p6"; Command::MAX_KEYS].join(" > ");
+        build_command(&command).unwrap();
+        build_command(&format!("{} > p6", command)).unwrap_err();
+    }
+
+    #[test]
+    fn command_parse_fail() {
+        // キー入力ボタン部分に隙間ができるとだめ
+        build_command(r#"h4(60)[8]>r6[10]>pC 6[20]"#).unwrap_err();
+    }
+
+    #[test]
+    fn command_parse_error_kind() {
+        // 解析エラーと検証エラーを区別できる
+        match build_command("p2 > pX") {
+            Err(Error::Parse(ParseError::NotComplete { rest })) => assert_eq!(rest, "> pX"),
+            other => panic!("{:?}", other),
+        }
+        match build_command("p2 > $qcf") {
+            Err(Error::Parse(ParseError::UndefinedMacro { name })) => assert_eq!(name, "qcf"),
+            other => panic!("{:?}", other),
+        }
+        match build_command("h4(0)[10] > p6") {
+            Err(Error::Validation(diagnostic)) => assert_eq!(diagnostic.code(), "E001"),
+            other => panic!("{:?}", other),
+        }
+    }
+}
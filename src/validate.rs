@@ -0,0 +1,287 @@
+#[cfg(feature = "alloc")]
+use crate::{
+    command::Command,
+    types::{button::Key, input::CommandKey},
+};
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+// 検証項目
+// コードは変更しない
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    // h4(0)
+    ZeroHold,
+    // p6[0], pA*4{0}
+    ZeroBuffer,
+    // pA*0
+    ZeroMashCount,
+    // ... @ 0
+    ZeroDuration,
+    // p46, p28, p39
+    ConflictingDirections,
+    // h5(60)
+    NeutralHold,
+    // n6 > f6
+    AdjacentOnOff,
+}
+
+impl Lint {
+    pub fn code(self) -> &'static str {
+        match self {
+            Lint::ZeroHold => "E001",
+            Lint::ZeroBuffer => "E002",
+            Lint::ZeroMashCount => "E003",
+            Lint::ZeroDuration => "E004",
+            Lint::ConflictingDirections => "E005",
+            Lint::NeutralHold => "W001",
+            Lint::AdjacentOnOff => "W002",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            Lint::NeutralHold | Lint::AdjacentOnOff => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Lint::ZeroHold => "hold frame must be greater than 0",
+            Lint::ZeroBuffer => "buffer frame must be greater than 0",
+            Lint::ZeroMashCount => "mash count must be greater than 0",
+            Lint::ZeroDuration => "max duration must be greater than 0",
+            Lint::ConflictingDirections => "key contains opposing stick directions",
+            Lint::NeutralHold => "holding neutral is always satisfied",
+            Lint::AdjacentOnOff => "on and off of the same key are adjacent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub lint: Lint,
+    // 何番目のキー入力か (コマンド全体なら None)
+    pub step: Option<usize>,
+}
+
+impl Diagnostic {
+    pub fn code(&self) -> &'static str {
+        self.lint.code()
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.lint.severity()
+    }
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.step {
+            Some(step) => write!(
+                f,
+                "{}: {} (step {})",
+                self.code(),
+                self.lint.message(),
+                step
+            ),
+            None => write!(f, "{}: {}", self.code(), self.lint.message()),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) fn validate(command: &Command) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut push = |lint, step| diagnostics.push(Diagnostic { lint, step });
+
+    let keys = command.keys().collect::<Vec<_>>();
+    for (step, key) in keys.iter().enumerate() {
+        let step = Some(step);
+        match **key {
+            CommandKey::Push { buffer_frame, .. } | CommandKey::Release { buffer_frame, .. } => {
+                if buffer_frame == Some(0) {
+                    push(Lint::ZeroBuffer, step);
+                }
+            }
+            CommandKey::Hold {
+                key,
+                buffer_frame,
+                hold_frame,
+                ..
+            } => {
+                if hold_frame == Some(0) {
+                    push(Lint::ZeroHold, step);
+                }
+                if buffer_frame == Some(0) {
+                    push(Lint::ZeroBuffer, step);
+                }
+                if key - Key::BUTTONS == Key::NEUTRAL {
+                    push(Lint::NeutralHold, step);
+                }
+            }
+            CommandKey::Mash {
+                count,
+                window_frame,
+                ..
+            } => {
+                if count == 0 {
+                    push(Lint::ZeroMashCount, step);
+                }
+                if window_frame == Some(0) {
+                    push(Lint::ZeroBuffer, step);
+                }
+            }
+            CommandKey::On { .. } | CommandKey::Off { .. } => {}
+        }
+
+        // 離しは反対方向を並べてもよい
+        let off = matches!(**key, CommandKey::Off { .. });
+        if !off && has_opposing_directions(key.key()) {
+            push(Lint::ConflictingDirections, step);
+        }
+    }
+
+    for (step, pair) in keys.windows(2).enumerate() {
+        match (*pair[0], *pair[1]) {
+            (CommandKey::On { key: a }, CommandKey::Off { key: b })
+            | (CommandKey::Off { key: a }, CommandKey::On { key: b })
+                if a == b =>
+            {
+                push(Lint::AdjacentOnOff, Some(step + 1))
+            }
+            _ => {}
+        }
+    }
+
+    if command.max_duration() == Some(0) {
+        push(Lint::ZeroDuration, None);
+    }
+
+    diagnostics
+}
+
+// 左右または上下の反対方向を同時に含む
+// 斜めはそれを構成する左右と上下の両方として扱う
+#[cfg(feature = "alloc")]
+fn has_opposing_directions(key: Key) -> bool {
+    let forward = Key::FORWARD | Key::FU | Key::FD;
+    let backward = Key::BACKWARD | Key::BU | Key::BD;
+    let up = Key::UP | Key::FU | Key::BU;
+    let down = Key::DOWN | Key::FD | Key::BD;
+    (key.intersects(forward) && key.intersects(backward))
+        || (key.intersects(up) && key.intersects(down))
+}
+
+// テストは記法の解析を使うので std のときだけ
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn codes(keys: Vec<CommandKey>) -> Vec<&'static str> {
+        validate(&Command::new(keys))
+            .iter()
+            .map(|d| d.code())
+            .collect()
+    }
+
+    #[test]
+    fn validate_ok() {
+        let command = crate::types::build_command("c4(60,3)[10] > p6 > pA*3{20} @ 90").unwrap();
+        assert!(command.validate().is_empty());
+    }
+
+    #[test]
+    fn validate_errors() {
+        assert_eq!(
+            codes(vec![CommandKey::Hold {
+                key: Key::BACKWARD,
+                buffer_frame: Some(0),
+                hold_frame: Some(0),
+                grace_frame: None,
+                direction_class: false,
+            }]),
+            vec!["E001", "E002"]
+        );
+        assert_eq!(
+            codes(vec![CommandKey::Mash {
+                key: Key::A,
+                count: 0,
+                window_frame: Some(0),
+            }]),
+            vec!["E003", "E002"]
+        );
+        assert_eq!(
+            codes(vec![CommandKey::Push {
+                key: Key::FORWARD | Key::BACKWARD | Key::A,
+                buffer_frame: None,
+            }]),
+            vec!["E005"]
+        );
+        assert_eq!(
+            codes(vec![CommandKey::Push {
+                key: Key::FD | Key::FU,
+                buffer_frame: None,
+            }]),
+            vec!["E005"]
+        );
+        let command = Command::new(vec![CommandKey::On { key: Key::A }]).with_max_duration(Some(0));
+        assert_eq!(command.validate()[0].code(), "E004");
+        assert_eq!(command.validate()[0].step, None);
+    }
+
+    #[test]
+    fn validate_multiple_directions() {
+        // 反対方向を含まなければ複数の方向でもよい
+        for notation in &["p26", "p5 > p6", "p56A", "h23(10)", "p3 > p36"] {
+            let command = crate::types::build_command(notation).unwrap();
+            assert!(command.validate().is_empty(), "{}", notation);
+        }
+        for notation in &["p46", "p28", "p19", "p37"] {
+            let err = crate::types::build_command(notation).unwrap_err();
+            assert!(err.to_string().contains("E005"), "{}", notation);
+        }
+    }
+
+    #[test]
+    fn validate_warnings() {
+        let diagnostics = validate(&Command::new(vec![
+            CommandKey::Hold {
+                key: Key::NEUTRAL,
+                buffer_frame: None,
+                hold_frame: Some(60),
+                grace_frame: None,
+                direction_class: false,
+            },
+            CommandKey::On { key: Key::FORWARD },
+            CommandKey::Off { key: Key::FORWARD },
+        ]));
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic {
+                    lint: Lint::NeutralHold,
+                    step: Some(0)
+                },
+                Diagnostic {
+                    lint: Lint::AdjacentOnOff,
+                    step: Some(2)
+                },
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.severity() == Severity::Warning));
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "W002: on and off of the same key are adjacent (step 2)"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+// 1F ごとの判定でヒープを確保しないこと
+use command_parser::{Command, Key, Matcher, Timing};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+// テストハーネスの別スレッドの確保を数えないようにスレッドごとに数える
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+const COMMANDS: &[&str] = &[
+    "p2 > p3 > p6 > pA",
+    "c4(45)[10] > p6 > pA",
+    "h4(30,3)[10] > p6 > pC",
+    "p6 > p3 > p2 > p1 > p4 > p7 > p8 > pA @ 30",
+    "pA*5{30}",
+    "dash6[10]",
+    "p2 > r2[5] > n6 > f8",
+];
+
+#[test]
+fn judge_without_allocation() {
+    let commands = COMMANDS
+        .iter()
+        .map(|command| Command::build(command).unwrap())
+        .collect::<Vec<_>>();
+    let inputs = commands
+        .iter()
+        .flat_map(|command| {
+            command
+                .generate_inputs(Timing::Random { seed: 1 }, 10, 10)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let mut matcher = Matcher::new(120, 10, 10).with_charge_retain(5);
+    let mut history = [Key::empty(); 120];
+
+    let mut matched = 0;
+    let count = allocations(|| {
+        for (frame, &input) in inputs.iter().enumerate() {
+            matcher.update(input);
+
+            // 固定長の履歴を古い順にずらして使う
+            history.rotate_left(1);
+            history[history.len() - 1] = input;
+            let len = (frame + 1).min(history.len());
+            let history = &history[history.len() - len..];
+
+            for command in &commands {
+                let result = matcher.judge(command);
+                matched += result.is_some() as usize;
+                command.judge_slice(history, 10, 10);
+            }
+        }
+    });
+
+    assert_eq!(count, 0);
+    assert!(matched >= commands.len());
+}
+
+#[test]
+fn rollback_without_allocation() {
+    let command = Command::build("c4(45)[10] > p6 > pA").unwrap();
+    let inputs = command
+        .generate_inputs(Timing::Random { seed: 1 }, 10, 10)
+        .unwrap();
+    let mut matcher = Matcher::new(120, 10, 10).with_charge_retain(5);
+    // 保存先は保持数分を確保してから使い回す
+    let mut states = (0..8).map(|_| matcher.save_state()).collect::<Vec<_>>();
+
+    let mut matched = 0;
+    let count = allocations(|| {
+        for (frame, &input) in inputs.iter().enumerate() {
+            let state = &mut states[frame % 8];
+            matcher.save_state_into(state);
+            matcher.update(input);
+            // 1F 前に戻してやり直す
+            matcher.load_state(state);
+            matcher.update(input);
+            matched += matcher.judge(&command).is_some() as usize;
+        }
+    });
+
+    assert_eq!(count, 0);
+    assert!(matched > 0);
+}
+
+#[test]
+fn counting_allocator_counts() {
+    assert!(allocations(|| drop(vec![Key::A; 4])) > 0);
+}
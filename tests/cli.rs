@@ -0,0 +1,189 @@
+// command-parser-cli の各サブコマンドと終了コード
+// 0: 成功, 1: 検証や整形の確認で失敗, 2: 引数や入力のエラー
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+const MOVE_LIST: &str = r#"(
+    moves: [
+        (name: "hadouken", command: "p2 > p3 > p6 > pA", priority: 10),
+        (name: "dash", command: "dash6[12]", category: System),
+    ],
+)
+"#;
+
+fn cli(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_command-parser-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+// テストごとに別のファイルに書き出す
+fn write_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("command-parser-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn unknown_subcommand() {
+    assert_eq!(cli(&["build"], "").status.code(), Some(2));
+    assert_eq!(cli(&[], "").status.code(), Some(2));
+    assert_eq!(cli(&["help"], "").status.code(), Some(0));
+}
+
+#[test]
+fn check() {
+    let path = write_file("check_ok.ron", MOVE_LIST);
+    let output = cli(&["check", path.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+
+    // 警告は --deny-warnings のときだけ失敗
+    let path = write_file(
+        "check_warning.ron",
+        r#"(moves: [(name: "neutral", command: "h5(60)")])"#,
+    );
+    let output = cli(&["check", path.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("neutral: warning: W001"));
+    let output = cli(&["check", "--deny-warnings", path.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(1));
+
+    // 読み込めない技リスト
+    let path = write_file(
+        "check_error.ron",
+        r#"(moves: [(name: "zero", command: "h4(0)")])"#,
+    );
+    let output = cli(&["check", path.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("error"));
+
+    assert_eq!(cli(&["check"], "").status.code(), Some(2));
+    assert_eq!(cli(&["check", "--fix", "a.ron"], "").status.code(), Some(2));
+}
+
+#[test]
+fn fmt_notation() {
+    let path = write_file("fmt.txt", "h4(60)[10]>p6 [10]>   pC\n");
+    let file = path.to_str().unwrap();
+    assert_eq!(cli(&["fmt", "--check", file], "").status.code(), Some(1));
+    assert_eq!(cli(&["fmt", file], "").status.code(), Some(0));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "h4(60)[10]\n> p6[10]\n> pC\n"
+    );
+    assert_eq!(cli(&["fmt", "--check", file], "").status.code(), Some(0));
+
+    assert_eq!(cli(&["fmt", "--compact", file], "").status.code(), Some(0));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "h4(60)[10] > p6[10] > pC\n"
+    );
+
+    let path = write_file("fmt_error.txt", "p6 > pX\n");
+    assert_eq!(
+        cli(&["fmt", path.to_str().unwrap()], "").status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn fmt_move_list() {
+    // command の記法だけを整形し，それ以外はそのまま残す
+    let source = MOVE_LIST.replace("p2 > p3 > p6 > pA", "p2>p3 >p6>pA");
+    let path = write_file("fmt_move_list.ron", &source);
+    let file = path.to_str().unwrap();
+    assert_eq!(cli(&["fmt", "--check", file], "").status.code(), Some(1));
+    assert_eq!(cli(&["fmt", file], "").status.code(), Some(0));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), MOVE_LIST);
+    assert_eq!(cli(&["fmt", "--check", file], "").status.code(), Some(0));
+
+    // 生文字列の記法も整形する
+    let path = write_file(
+        "fmt_move_list_raw.ron",
+        "(moves: [(name: \"quote\", command: r#\"p2>pA\"#)])",
+    );
+    assert_eq!(
+        cli(&["fmt", path.to_str().unwrap()], "").status.code(),
+        Some(0)
+    );
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "(moves: [(name: \"quote\", command: \"p2 > pA\")])"
+    );
+
+    let path = write_file("fmt_move_list_error.ron", "(moves: [(name: \"x\")])");
+    assert_eq!(
+        cli(&["fmt", path.to_str().unwrap()], "").status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn explain() {
+    let output = cli(&["explain", "dash6[10]"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        "1. press 6 within 10 frames\n2. stick returns to neutral\n3. press 6\n"
+    );
+
+    // 警告は表示するが成功
+    let output = cli(&["explain", "h5(60)"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("note: W001"));
+
+    assert_eq!(cli(&["explain", "p6 > pX"], "").status.code(), Some(2));
+}
+
+#[test]
+fn convert() {
+    let output = cli(&["convert", "p2 > pA @ 30"], "");
+    assert_eq!(output.status.code(), Some(0));
+    let structured = stdout(&output);
+    assert_eq!(
+        structured,
+        "(keys:[Push(key:[\"DOWN\",],),Push(key:[\"A\",],),],max_duration:Some(30),)\n"
+    );
+
+    // 構造化形式は標準入力からも読める
+    let output = cli(&["convert", "-"], &structured);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "\"p2>pA@30\"\n");
+
+    let output = cli(&["convert", "--to", "notation", "p2 > pA"], "");
+    assert_eq!(stdout(&output), "\"p2>pA\"\n");
+
+    assert_eq!(
+        cli(&["convert", "--to", "json", "p2"], "").status.code(),
+        Some(2)
+    );
+    // 値のない --to は既定の変換にしない
+    let output = cli(&["convert", "p2", "--to"], "");
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing value for --to"));
+    assert_eq!(
+        cli(&["convert", "[Push(key: [])]"], "").status.code(),
+        Some(2)
+    );
+}
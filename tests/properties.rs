@@ -0,0 +1,144 @@
+// 記法の解析と判定に対するプロパティテスト
+use command_parser::{
+    deserialize_structured, serialize, serialize_structured, Command, CommandKey, Key, Matcher,
+};
+use proptest::prelude::*;
+
+fn key() -> impl Strategy<Value = Key> {
+    let direction = prop::sample::select(vec![
+        Key::empty(),
+        Key::FORWARD,
+        Key::BACKWARD,
+        Key::UP,
+        Key::DOWN,
+        Key::FD,
+        Key::FU,
+        Key::BD,
+        Key::BU,
+        Key::NEUTRAL,
+    ]);
+    (direction, 0..16u16)
+        .prop_map(|(direction, buttons)| direction | Key::from_bits_truncate(buttons))
+        .prop_filter("empty key", |key| !key.is_empty())
+}
+
+// 検証エラーにならない値
+fn frame() -> impl Strategy<Value = Option<u32>> {
+    prop::option::of(1..200u32)
+}
+
+fn command_key() -> impl Strategy<Value = CommandKey> {
+    prop_oneof![
+        (key(), frame()).prop_map(|(key, buffer_frame)| CommandKey::Push { key, buffer_frame }),
+        (key(), frame()).prop_map(|(key, buffer_frame)| CommandKey::Release { key, buffer_frame }),
+        (
+            key(),
+            frame(),
+            frame(),
+            prop::option::of(0..10u32),
+            any::<bool>()
+        )
+            .prop_map(
+                |(key, buffer_frame, hold_frame, grace_frame, direction_class)| CommandKey::Hold {
+                    key,
+                    buffer_frame,
+                    hold_frame,
+                    grace_frame,
+                    direction_class,
+                }
+            ),
+        key().prop_map(|key| CommandKey::On { key }),
+        key().prop_map(|key| CommandKey::Off { key }),
+        (key(), 1..10u32, frame()).prop_map(|(key, count, window_frame)| CommandKey::Mash {
+            key,
+            count,
+            window_frame,
+        }),
+    ]
+}
+
+fn command() -> impl Strategy<Value = Command> {
+    (prop::collection::vec(command_key(), 1..8), frame())
+        .prop_map(|(keys, max_duration)| Command::new(keys).with_max_duration(max_duration))
+}
+
+// 検証しない任意の値
+fn any_command() -> impl Strategy<Value = Command> {
+    let frame = || prop::option::of(any::<u32>());
+    let key = || any::<u16>().prop_map(Key::from_bits_truncate);
+    let command_key = prop_oneof![
+        (key(), frame()).prop_map(|(key, buffer_frame)| CommandKey::Push { key, buffer_frame }),
+        (key(), frame()).prop_map(|(key, buffer_frame)| CommandKey::Release { key, buffer_frame }),
+        (key(), frame(), frame(), frame(), any::<bool>()).prop_map(
+            |(key, buffer_frame, hold_frame, grace_frame, direction_class)| CommandKey::Hold {
+                key,
+                buffer_frame,
+                hold_frame,
+                grace_frame,
+                direction_class,
+            }
+        ),
+        key().prop_map(|key| CommandKey::On { key }),
+        key().prop_map(|key| CommandKey::Off { key }),
+        (key(), 0..10u32, frame()).prop_map(|(key, count, window_frame)| CommandKey::Mash {
+            key,
+            count,
+            window_frame,
+        }),
+    ];
+    (prop::collection::vec(command_key, 0..8), frame())
+        .prop_map(|(keys, max_duration)| Command::new(keys).with_max_duration(max_duration))
+}
+
+fn inputs() -> impl Strategy<Value = Vec<Key>> {
+    prop::collection::vec(any::<u16>().prop_map(Key::from_bits_truncate), 0..300)
+}
+
+proptest! {
+    #[test]
+    fn build_never_panics(notation in ".*") {
+        let _ = Command::build(&notation);
+    }
+
+    #[test]
+    fn build_notation_like_never_panics(
+        notation in r"([prhcnf]|tap|dash)?[1-9A-D]{0,3}(\([0-9]{0,3}(,[0-9]{0,3})?\))?(\[[0-9]{0,4}\])?(\*[0-9]{0,2}(\{[0-9]{0,3}\})?)?( ?> ?[prhcnf][1-9A-D]{1,2}){0,4}( ?@ ?[0-9]{0,3})?"
+    ) {
+        let _ = Command::build(&notation);
+    }
+
+    #[test]
+    fn notation_round_trip(command in command()) {
+        prop_assume!(command.validate().iter().all(|d| d.code().starts_with('W')));
+        let serialized = serialize(&command).unwrap();
+        let deserialized: Command = ron::de::from_str(&serialized).unwrap();
+        prop_assert_eq!(deserialized, command);
+    }
+
+    #[test]
+    fn structured_round_trip(command in command()) {
+        prop_assume!(command.validate().iter().all(|d| d.code().starts_with('W')));
+        let serialized = serialize_structured(&command).unwrap();
+        let deserialized = deserialize_structured(&serialized).unwrap();
+        prop_assert_eq!(deserialized, command);
+    }
+
+    #[test]
+    fn judge_never_panics(
+        command in any_command(),
+        inputs in inputs(),
+        default_buffer in prop_oneof![0..30u32, Just(u32::MAX)],
+        default_hold in prop_oneof![0..90u32, Just(u32::MAX)],
+    ) {
+        let _ = command.judge(inputs.iter().copied(), default_buffer, default_hold);
+
+        let mut matcher = Matcher::new(120, default_buffer, default_hold);
+        for input in inputs {
+            matcher.update(input);
+            prop_assert_eq!(
+                matcher.judge(&command).is_some(),
+                command.judge_inputs(matcher.inputs(), default_buffer, default_hold)
+            );
+        }
+    }
+}